@@ -1,31 +1,333 @@
-use reqwest::blocking::Client;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use std::error::Error as StdError;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use crate::api::models::*;
+use crate::api::pagination::{PageRequest, SessionPages, DEFAULT_PAGE_SIZE};
+use crate::api::retry::RetryPolicy;
+use crate::api::stream::MessageStream;
+use crate::api::upload::{Attachment, UploadProgress};
+
+/// Base URL of the public Devin API
+pub const DEFAULT_API_URL: &str = "https://api.devin.ai";
+
+/// How long `check_connection` waits for the API unless a timeout is configured
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a streamed reply may take in total unless a timeout is configured;
+/// the client's default timeout would cut it off
+const STREAM_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Failed to connect to API: {0}")]
     ConnectionError(String),
     
+    #[error("Failed to resolve API host: {0}")]
+    DnsError(String),
+    
+    #[error("TLS handshake with API failed: {0}")]
+    TlsError(String),
+    
+    #[error("API request timed out: {0}")]
+    Timeout(String),
+    
+    #[error("API rejected the token (status {status}){}", detail_suffix(.message))]
+    Unauthorized {
+        status: u16,
+        message: Option<String>,
+        request_id: Option<String>,
+    },
+    
+    #[error("Not found{}", detail_suffix(.message))]
+    NotFound {
+        message: Option<String>,
+        request_id: Option<String>,
+    },
+    
+    #[error("Rate limited by API{}", retry_after_suffix(.retry_after))]
+    RateLimited {
+        retry_after: Option<u64>,
+        request_id: Option<String>,
+    },
+    
+    #[error("API rejected the request: {details}")]
+    Validation {
+        details: String,
+        request_id: Option<String>,
+    },
+    
+    #[error("API is unavailable (status {status}){}", body_suffix(.body))]
+    Server {
+        status: u16,
+        body: String,
+        request_id: Option<String>,
+    },
+    
     #[error("API request failed: {0}")]
     RequestError(String),
     
+    #[error("Invalid API client configuration: {0}")]
+    InvalidConfig(String),
+    
     #[error("Failed to parse API response: {0}")]
     ParseError(String),
 }
 
+fn detail_suffix(message: &Option<String>) -> String {
+    match message {
+        Some(message) => format!(": {}", message),
+        None => String::new(),
+    }
+}
+
+fn body_suffix(body: &str) -> String {
+    const MAX_BODY_CHARS: usize = 200;
+    
+    let message = error_message(body).unwrap_or_else(|| body.trim().to_string());
+    if message.is_empty() {
+        return String::new();
+    }
+    if message.chars().count() > MAX_BODY_CHARS {
+        let truncated: String = message.chars().take(MAX_BODY_CHARS).collect();
+        return format!(": {}...", truncated);
+    }
+    format!(": {}", message)
+}
+
+fn retry_after_suffix(retry_after: &Option<u64>) -> String {
+    match retry_after {
+        Some(seconds) => format!(", retry after {}s", seconds),
+        None => String::new(),
+    }
+}
+
+impl ApiError {
+    /// Short machine-readable name for the error kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApiError::ConnectionError(_) => "connection",
+            ApiError::DnsError(_) => "dns",
+            ApiError::TlsError(_) => "tls",
+            ApiError::Timeout(_) => "timeout",
+            ApiError::Unauthorized { .. } => "unauthorized",
+            ApiError::NotFound { .. } => "not_found",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::Validation { .. } => "validation",
+            ApiError::Server { .. } => "server",
+            ApiError::RequestError(_) => "request",
+            ApiError::InvalidConfig(_) => "config",
+            ApiError::ParseError(_) => "parse",
+        }
+    }
+    
+    /// Process exit code scripts can branch on
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApiError::Unauthorized { .. } => 3,
+            ApiError::NotFound { .. } => 4,
+            ApiError::RateLimited { .. } => 5,
+            ApiError::Validation { .. } => 6,
+            ApiError::Server { .. } => 7,
+            ApiError::ConnectionError(_)
+            | ApiError::DnsError(_)
+            | ApiError::TlsError(_)
+            | ApiError::Timeout(_) => 8,
+            ApiError::RequestError(_) | ApiError::InvalidConfig(_) | ApiError::ParseError(_) => 1,
+        }
+    }
+    
+    /// The request id reported by the API, if any
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ApiError::Unauthorized { request_id, .. }
+            | ApiError::NotFound { request_id, .. }
+            | ApiError::RateLimited { request_id, .. }
+            | ApiError::Validation { request_id, .. }
+            | ApiError::Server { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a transport-level failure from reqwest
+fn classify_send_error(error: reqwest::Error) -> ApiError {
+    // reqwest only exposes timeouts and connect failures directly, so the
+    // underlying hyper/io/tls error chain is inspected for the rest
+    let mut details = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        details.push_str(": ");
+        details.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    let lowered = details.to_lowercase();
+    
+    if error.is_timeout() {
+        ApiError::Timeout(details)
+    } else if lowered.contains("dns error") || lowered.contains("failed to lookup address") {
+        ApiError::DnsError(details)
+    } else if lowered.contains("certificate") || lowered.contains("tls") || lowered.contains("ssl") {
+        ApiError::TlsError(details)
+    } else {
+        ApiError::ConnectionError(details)
+    }
+}
+
+/// Extract a human-readable message from an API error payload
+///
+/// The API reports errors as `{"detail": ...}`, where `detail` is either a
+/// string or a list of validation entries with a `msg` field. `message` and
+/// `error` keys are accepted as well.
+fn error_message(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let detail = ["detail", "message", "error"]
+        .iter()
+        .find_map(|key| value.get(*key))?;
+    
+    match detail {
+        serde_json::Value::String(message) => Some(message.clone()),
+        serde_json::Value::Array(entries) => {
+            let messages: Vec<String> = entries
+                .iter()
+                .map(|entry| match (entry.get("loc"), entry.get("msg")) {
+                    (Some(serde_json::Value::Array(loc)), Some(serde_json::Value::String(msg))) => {
+                        let field: Vec<String> = loc.iter()
+                            .map(|part| part.as_str().map(str::to_string).unwrap_or_else(|| part.to_string()))
+                            .collect();
+                        format!("{}: {}", field.join("."), msg)
+                    }
+                    (_, Some(serde_json::Value::String(msg))) => msg.clone(),
+                    _ => entry.to_string(),
+                })
+                .collect();
+            Some(messages.join("; "))
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+/// Parse the `Retry-After` header of a response
+fn retry_after(response: &Response) -> Option<Duration> {
+    response.headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, SystemTime::now()))
+}
+
+/// Parse a `Retry-After` value, either seconds or an HTTP date
+///
+/// A date in the past means the request can be retried right away.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Turn a non-2xx response into the matching error
+fn check_status(response: Response) -> Result<Response, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    
+    let header = |name: &str| {
+        response.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let request_id = header("x-request-id");
+    let retry_after = retry_after(&response).map(|delay| delay.as_secs());
+    let body = response.text().unwrap_or_default();
+    let message = error_message(&body);
+    
+    Err(match status.as_u16() {
+        401 | 403 => ApiError::Unauthorized {
+            status: status.as_u16(),
+            message,
+            request_id,
+        },
+        404 => ApiError::NotFound { message, request_id },
+        429 => ApiError::RateLimited { retry_after, request_id },
+        400 | 422 => ApiError::Validation {
+            details: message.unwrap_or_else(|| status.to_string()),
+            request_id,
+        },
+        code if status.is_server_error() => ApiError::Server {
+            status: code,
+            body,
+            request_id,
+        },
+        _ => ApiError::RequestError(format!(
+            "API returned status: {}{}",
+            status,
+            detail_suffix(&message)
+        )),
+    })
+}
+
+/// Connection settings used to build the underlying HTTP client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientOptions {
+    /// Base URL of the API, defaults to `DEFAULT_API_URL`
+    pub api_url: Option<String>,
+    /// Total timeout for a single request
+    pub timeout: Option<Duration>,
+    /// Timeout for establishing a connection
+    pub connect_timeout: Option<Duration>,
+    /// Proxy URL used for all requests
+    pub proxy: Option<String>,
+    /// PEM file with additional trusted CA certificates
+    pub ca_cert: Option<PathBuf>,
+    /// Attempts per request, including the first
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry
+    pub retry_base_delay: Option<Duration>,
+    /// Longest delay between two attempts
+    pub retry_max_delay: Option<Duration>,
+    /// Randomize retry delays
+    pub retry_jitter: Option<bool>,
+    /// Also retry requests that are not idempotent
+    pub retry_non_idempotent: Option<bool>,
+}
+
+impl ClientOptions {
+    /// The default retry policy with the settings given here
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(default.max_attempts),
+            base_delay: self.retry_base_delay.unwrap_or(default.base_delay),
+            max_delay: self.retry_max_delay.unwrap_or(default.max_delay),
+            jitter: self.retry_jitter.unwrap_or(default.jitter),
+            retry_non_idempotent: self.retry_non_idempotent.unwrap_or(default.retry_non_idempotent),
+        }
+    }
+}
+
 /// Client for interacting with the Devin API
+#[derive(Clone)]
 pub struct ApiClient {
     client: Client,
     api_token: String,
     api_url: String,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
 }
 
 impl ApiClient {
     /// Create a new API client with the given token
     pub fn new(token: &str) -> Self {
-        Self::new_with_url(token, "https://api.devin.ai")
+        Self::new_with_url(token, DEFAULT_API_URL)
     }
     
     /// Create a new API client with a custom API URL
@@ -34,27 +336,98 @@ impl ApiClient {
             client: Client::new(),
             api_token: token.to_string(),
             api_url: url.to_string(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+    
+    /// Create a new API client with custom URL, timeouts, proxy and CA bundle
+    pub fn with_options(token: &str, options: &ClientOptions) -> Result<Self, ApiError> {
+        let mut builder = Client::builder();
+        
+        if let Some(timeout) = options.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = options.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = &options.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| ApiError::InvalidConfig(format!("invalid proxy '{}': {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &options.ca_cert {
+            let pem = fs::read(path)
+                .map_err(|e| ApiError::InvalidConfig(format!("cannot read CA bundle {}: {}", path.display(), e)))?;
+            let certificate = reqwest::Certificate::from_pem(&pem)
+                .map_err(|e| ApiError::InvalidConfig(format!("invalid CA bundle {}: {}", path.display(), e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        
+        let client = builder.build()
+            .map_err(|e| ApiError::InvalidConfig(e.to_string()))?;
+        let api_url = options.api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        
+        Ok(Self {
+            client,
+            api_token: token.to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            timeout: options.timeout,
+            retry_policy: options.retry_policy(),
+        })
+    }
+    
+    /// Use a custom retry policy for all requests
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+    
+    /// Send a request, retrying transient failures according to the retry policy
+    ///
+    /// `build` is called once per attempt since a blocking request can't be cloned
+    /// after its body has been consumed.
+    fn send(&self, idempotent: bool, build: impl Fn() -> RequestBuilder) -> Result<Response, ApiError> {
+        let mut attempt = 1;
+        loop {
+            let (error, delay_hint) = match build().send() {
+                Ok(response) => {
+                    let delay_hint = retry_after(&response);
+                    match check_status(response) {
+                        Ok(response) => return Ok(response),
+                        Err(e) => (e, delay_hint),
+                    }
+                }
+                Err(e) => (classify_send_error(e), None),
+            };
+            
+            if !self.retry_policy.should_retry(&error, attempt, idempotent) {
+                return Err(error);
+            }
+            
+            thread::sleep(self.retry_policy.delay(attempt, delay_hint));
+            attempt += 1;
         }
     }
     
-    /// Check if the API is reachable
+    /// Check that the API is reachable and accepts the token
+    ///
+    /// Issues a minimal authenticated `list_sessions` request so DNS, TLS,
+    /// timeout, invalid-token and outage failures all surface as distinct errors.
     pub fn check_connection(&self) -> Result<(), ApiError> {
-        // In a real implementation, this would make an actual API call
-        // For now, we'll just return success if we have a token
         if self.api_token.is_empty() {
             return Err(ApiError::ConnectionError("API token is empty".to_string()));
         }
         
-        // In a real implementation, we would use self.client to make an HTTP request
-        // For example:
-        // let response = self.client.get(&format!("{}/health", self.api_url))
-        //     .header("Authorization", format!("Bearer {}", self.api_token))
-        //     .send()
-        //     .map_err(|e| ApiError::ConnectionError(e.to_string()))?;
-        //
-        // if !response.status().is_success() {
-        //     return Err(ApiError::RequestError(format!("API returned status: {}", response.status())));
-        // }
+        let url = format!("{}/v1/sessions", self.api_url);
+        let response = self.client.get(&url)
+            .query(&[("limit", "1")])
+            .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+            .timeout(self.timeout.unwrap_or(PROBE_TIMEOUT))
+            .send()
+            .map_err(classify_send_error)?;
+        
+        check_status(response)?;
         
         Ok(())
     }
@@ -76,7 +449,6 @@ impl ApiClient {
         Ok(format!("Response from {}{}", self.api_url, endpoint))
     }
     
-    #[cfg(test)]
     /// Get the API URL
     pub fn get_api_url(&self) -> &str {
         &self.api_url
@@ -95,28 +467,40 @@ impl ApiClient {
         format!("{}...{}", first, last)
     }
     
-    /// Create a new session with an initial message
-    pub fn create_session(&self, message: &str) -> Result<String, ApiError> {
+    /// Create a new session
+    ///
+    /// Only idempotent requests are retried after failures the server may
+    /// already have acted on, since a retry could otherwise start a second session.
+    pub fn create_session(&self, request: &CreateSessionRequest) -> Result<CreateSessionResponse, ApiError> {
         let url = format!("{}/v1/sessions", self.api_url);
-        let request = CreateSessionRequest {
-            prompt: message.to_string(),
-        };
         
-        let response = self.client.post(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
-            .header(CONTENT_TYPE, "application/json")
-            .json(&request)
-            .send()
-            .map_err(|e| ApiError::ConnectionError(e.to_string()))?;
+        let response = self.send(request.idempotent, || {
+            self.client.post(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+                .header(CONTENT_TYPE, "application/json")
+                .json(request)
+        })?;
         
-        if !response.status().is_success() {
-            return Err(ApiError::RequestError(format!("API returned status: {}", response.status())));
-        }
+        response.json()
+            .map_err(|e| ApiError::ParseError(e.to_string()))
+    }
+    
+    /// Upload a file to reference in a prompt, returning its URL
+    ///
+    /// Uploads are retried like reads: a retry at worst leaves an unused copy behind.
+    pub fn upload_attachment(&self, attachment: &Attachment, progress: Option<UploadProgress>) -> Result<String, ApiError> {
+        let url = format!("{}/v1/attachments", self.api_url);
         
-        let response_data: CreateSessionResponse = response.json()
+        let response = self.send(true, || {
+            self.client.post(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+                .multipart(Form::new().part("file", attachment.part(progress.clone())))
+        })?;
+        
+        let response_data: AttachmentResponse = response.json()
             .map_err(|e| ApiError::ParseError(e.to_string()))?;
         
-        Ok(response_data.session_id)
+        Ok(response_data.into_url())
     }
     
     /// Send a message to an existing session
@@ -126,16 +510,12 @@ impl ApiClient {
             message: message.to_string(),
         };
         
-        let response = self.client.post(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
-            .header(CONTENT_TYPE, "application/json")
-            .json(&request)
-            .send()
-            .map_err(|e| ApiError::ConnectionError(e.to_string()))?;
-        
-        if !response.status().is_success() {
-            return Err(ApiError::RequestError(format!("API returned status: {}", response.status())));
-        }
+        let response = self.send(false, || {
+            self.client.post(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+                .header(CONTENT_TYPE, "application/json")
+                .json(&request)
+        })?;
         
         let response_data: MessageResponse = response.json()
             .map_err(|e| ApiError::ParseError(e.to_string()))?;
@@ -143,48 +523,143 @@ impl ApiClient {
         Ok(response_data)
     }
     
+    /// Send a message and stream Devin's reply as it arrives
+    ///
+    /// Asks for server-sent events, and falls back to polling the session when
+    /// the API answers with a single reply that is not `done` yet.
+    pub fn stream_message(&self, session_id: &str, message: &str) -> Result<MessageStream, ApiError> {
+        let url = format!("{}/v1/sessions/{}/messages", self.api_url, session_id);
+        let request = SendMessageRequest {
+            message: message.to_string(),
+        };
+        
+        let response = self.send(false, || {
+            self.client.post(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+                .header(CONTENT_TYPE, "application/json")
+                .header(ACCEPT, "text/event-stream, application/json")
+                .timeout(self.timeout.unwrap_or(STREAM_TIMEOUT))
+                .json(&request)
+        })?;
+        
+        MessageStream::from_response(self.clone(), session_id, response)
+    }
+    
+    /// Stream a session's new replies until it stops running
+    pub fn follow_session(&self, session_id: &str) -> MessageStream {
+        MessageStream::follow(self.clone(), session_id)
+    }
+    
     /// List all sessions
     pub fn list_sessions(&self) -> Result<Vec<SessionDetails>, ApiError> {
+        self.sessions(DEFAULT_PAGE_SIZE).collect()
+    }
+    
+    /// Iterate over all sessions, fetching `page_size` sessions per request
+    pub fn sessions(&self, page_size: usize) -> SessionPages<'_> {
+        SessionPages::new(self, page_size)
+    }
+    
+    /// Fetch a single page of sessions
+    pub fn list_sessions_page(&self, page: &PageRequest) -> Result<ListSessionsResponse, ApiError> {
         let url = format!("{}/v1/sessions", self.api_url);
-        
-        let response = self.client.get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
-            .send()
-            .map_err(|e| ApiError::ConnectionError(e.to_string()))?;
-        
-        if !response.status().is_success() {
-            return Err(ApiError::RequestError(format!("API returned status: {}", response.status())));
+        let mut query = vec![("limit", page.limit.to_string())];
+        match &page.cursor {
+            Some(cursor) => query.push(("cursor", cursor.clone())),
+            None => query.push(("offset", page.offset.to_string())),
         }
         
-        let response_data: ListSessionsResponse = response.json()
+        let response = self.send(true, || {
+            self.client.get(&url)
+                .query(&query)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+        })?;
+        
+        let mut response_data: ListSessionsResponse = response.json()
             .map_err(|e| ApiError::ParseError(e.to_string()))?;
+        for session in &mut response_data.sessions {
+            self.fill_session_url(session);
+        }
         
-        Ok(response_data.sessions)
+        Ok(response_data)
     }
     
     /// Get details for a specific session
     pub fn get_session_details(&self, session_id: &str) -> Result<SessionDetails, ApiError> {
         let url = format!("{}/v1/sessions/{}", self.api_url, session_id);
         
-        let response = self.client.get(&url)
-            .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
-            .send()
-            .map_err(|e| ApiError::ConnectionError(e.to_string()))?;
-        
-        if !response.status().is_success() {
-            return Err(ApiError::RequestError(format!("API returned status: {}", response.status())));
-        }
+        let response = self.send(true, || {
+            self.client.get(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+        })?;
         
-        let response_data: SessionDetails = response.json()
+        let mut response_data: SessionDetails = response.json()
             .map_err(|e| ApiError::ParseError(e.to_string()))?;
+        self.fill_session_url(&mut response_data);
         
         Ok(response_data)
     }
+    
+    /// Web app address of a session, for when the API does not return one
+    ///
+    /// `api.<domain>` serves the web app at `app.<domain>`; other deployments
+    /// are assumed to serve both from the same host. The app leaves out the
+    /// `devin-` prefix of session IDs.
+    pub fn session_url(&self, session_id: &str) -> String {
+        let id = session_id.strip_prefix("devin-").unwrap_or(session_id);
+        let Ok(mut url) = reqwest::Url::parse(&self.api_url) else {
+            return format!("{}/sessions/{}", self.api_url.trim_end_matches('/'), id);
+        };
+        if let Some(domain) = url.host_str().and_then(|host| host.strip_prefix("api.")) {
+            let app_host = format!("app.{}", domain);
+            let _ = url.set_host(Some(&app_host));
+        }
+        url.set_path(&format!("sessions/{}", id));
+        url.set_query(None);
+        url.to_string()
+    }
+    
+    fn fill_session_url(&self, session: &mut SessionDetails) {
+        if session.url.is_none() {
+            session.url = Some(self.session_url(&session.session_id));
+        }
+    }
+    
+    /// Terminate a session
+    pub fn terminate_session(&self, session_id: &str) -> Result<(), ApiError> {
+        let url = format!("{}/v1/sessions/{}", self.api_url, session_id);
+        
+        self.send(true, || {
+            self.client.delete(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+        })?;
+        
+        Ok(())
+    }
+    
+    /// Replace the tags of a session
+    pub fn update_session_tags(&self, session_id: &str, tags: &[String]) -> Result<(), ApiError> {
+        let url = format!("{}/v1/sessions/{}/tags", self.api_url, session_id);
+        let request = UpdateTagsRequest {
+            tags: tags.to_vec(),
+        };
+        
+        self.send(true, || {
+            self.client.put(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", self.api_token))
+                .header(CONTENT_TYPE, "application/json")
+                .json(&request)
+        })?;
+        
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
     
     #[test]
     fn test_client_creation() {
@@ -203,14 +678,109 @@ mod tests {
         assert_eq!(client.api_url, url);
     }
     
+    #[test]
+    fn test_client_with_options() {
+        let options = ClientOptions {
+            api_url: Some("https://devin.internal.example.com/".to_string()),
+            timeout: Some(Duration::from_secs(5)),
+            connect_timeout: Some(Duration::from_secs(2)),
+            proxy: Some("http://proxy.example.com:3128".to_string()),
+            max_attempts: Some(5),
+            retry_jitter: Some(false),
+            ..ClientOptions::default()
+        };
+        let client = ApiClient::with_options("test-token", &options).unwrap();
+        assert_eq!(client.get_api_url(), "https://devin.internal.example.com");
+        assert_eq!(client.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(client.retry_policy, RetryPolicy {
+            max_attempts: 5,
+            jitter: false,
+            ..RetryPolicy::default()
+        });
+        
+        let client = ApiClient::with_options("test-token", &ClientOptions::default()).unwrap();
+        assert_eq!(client.get_api_url(), DEFAULT_API_URL);
+        assert_eq!(client.retry_policy, RetryPolicy::default());
+    }
+    
+    #[test]
+    fn test_client_with_invalid_options() {
+        let options = ClientOptions {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..ClientOptions::default()
+        };
+        match ApiClient::with_options("test-token", &options) {
+            Err(ApiError::InvalidConfig(msg)) => assert!(msg.contains("ca.pem")),
+            _ => panic!("Expected InvalidConfig"),
+        }
+        
+        let options = ClientOptions {
+            proxy: Some("not a url".to_string()),
+            ..ClientOptions::default()
+        };
+        assert!(matches!(
+            ApiClient::with_options("test-token", &options),
+            Err(ApiError::InvalidConfig(_))
+        ));
+    }
+    
     #[test]
     fn test_check_connection() {
-        let token = "test-token";
-        let client = ApiClient::new(token);
+        let mut server = mockito::Server::new();
+        let mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "1".into()))
+            .match_header("authorization", "Bearer test-token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"sessions":[]}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
         let result = client.check_connection();
+        
+        mock.assert();
         assert!(result.is_ok());
     }
     
+    #[test]
+    fn test_check_connection_invalid_token() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(401)
+            .create();
+        
+        let client = ApiClient::new_with_url("revoked-token", &server.url());
+        match client.check_connection() {
+            Err(ApiError::Unauthorized { status: 401, .. }) => {}
+            other => panic!("Expected Unauthorized, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_check_connection_server_error() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        match client.check_connection() {
+            Err(ApiError::Server { status: 503, .. }) => {}
+            other => panic!("Expected Server, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_check_connection_dns_failure() {
+        let client = ApiClient::new_with_url("test-token", "http://devin-api.invalid");
+        match client.check_connection() {
+            Err(ApiError::DnsError(_)) => {}
+            other => panic!("Expected DnsError, got {:?}", other),
+        }
+    }
+    
     #[test]
     fn test_check_connection_with_empty_token() {
         let token = "";
@@ -225,6 +795,360 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_get_session_details_not_found() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions/missing")
+            .with_status(404)
+            .with_header("x-request-id", "req-123")
+            .with_body(r#"{"detail":"Session not found"}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let error = client.get_session_details("missing").unwrap_err();
+        match &error {
+            ApiError::NotFound { message, request_id } => {
+                assert_eq!(message.as_deref(), Some("Session not found"));
+                assert_eq!(request_id.as_deref(), Some("req-123"));
+            }
+            other => panic!("Expected NotFound, got {:?}", other),
+        }
+        assert_eq!(error.kind(), "not_found");
+        assert_eq!(error.exit_code(), 4);
+        assert_eq!(error.request_id(), Some("req-123"));
+        assert!(error.to_string().contains("Session not found"));
+    }
+    
+    #[test]
+    fn test_rate_limited_with_retry_after() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(429)
+            .with_header("retry-after", "30")
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(RetryPolicy::none());
+        match client.list_sessions() {
+            Err(ApiError::RateLimited { retry_after, .. }) => {
+                assert_eq!(retry_after, Some(30));
+            }
+            other => panic!("Expected RateLimited, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_parse_retry_after() {
+        let now = httpdate::parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(parse_retry_after(" 30 ", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+    
+    #[test]
+    fn test_validation_error_details() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("POST", "/v1/sessions")
+            .with_status(422)
+            .with_body(r#"{"detail":[{"loc":["body","prompt"],"msg":"field required"}]}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        match client.create_session(&CreateSessionRequest::new("")) {
+            Err(ApiError::Validation { details, .. }) => {
+                assert_eq!(details, "body.prompt: field required");
+            }
+            other => panic!("Expected Validation, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_server_error_keeps_body() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("POST", "/v1/sessions/abc/messages")
+            .with_status(500)
+            .with_body("upstream exploded")
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        match client.send_message("abc", "hello") {
+            Err(ApiError::Server { status, body, request_id }) => {
+                assert_eq!(status, 500);
+                assert_eq!(body, "upstream exploded");
+                assert_eq!(request_id, None);
+            }
+            other => panic!("Expected Server, got {:?}", other),
+        }
+    }
+    
+    fn fast_retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+    
+    const SESSION_JSON: &str = r#"{"session_id":"abc","status":"running","created_at":"2024-01-01T00:00:00Z","updated_at":null}"#;
+    const CREATED_JSON: &str = r#"{"session_id":"abc","url":"https://app.devin.ai/sessions/abc","is_new_session":true}"#;
+    
+    #[test]
+    fn test_retries_transient_server_errors() {
+        let mut server = mockito::Server::new();
+        let outage = server.mock("GET", "/v1/sessions/abc")
+            .with_status(503)
+            .expect(2)
+            .create();
+        let success = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(SESSION_JSON)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(fast_retry_policy());
+        let details = client.get_session_details("abc").unwrap();
+        
+        outage.assert();
+        success.assert();
+        assert_eq!(details.session_id, "abc");
+    }
+    
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let mut server = mockito::Server::new();
+        let outage = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(502)
+            .expect(3)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(fast_retry_policy());
+        let result = client.list_sessions();
+        
+        outage.assert();
+        assert!(matches!(result, Err(ApiError::Server { status: 502, .. })));
+    }
+    
+    #[test]
+    fn test_create_session_is_not_retried_on_server_error() {
+        let mut server = mockito::Server::new();
+        let outage = server.mock("POST", "/v1/sessions")
+            .with_status(503)
+            .expect(1)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(fast_retry_policy());
+        let result = client.create_session(&CreateSessionRequest::new("hello"));
+        
+        outage.assert();
+        assert!(matches!(result, Err(ApiError::Server { status: 503, .. })));
+    }
+    
+    #[test]
+    fn test_create_session_retried_when_opted_in() {
+        let mut server = mockito::Server::new();
+        let outage = server.mock("POST", "/v1/sessions")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let success = server.mock("POST", "/v1/sessions")
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(RetryPolicy {
+                retry_non_idempotent: true,
+                ..fast_retry_policy()
+            });
+        let result = client.create_session(&CreateSessionRequest::new("hello"));
+        
+        outage.assert();
+        success.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_create_session_retried_after_rate_limit() {
+        let mut server = mockito::Server::new();
+        let rate_limited = server.mock("POST", "/v1/sessions")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create();
+        let success = server.mock("POST", "/v1/sessions")
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(fast_retry_policy());
+        let result = client.create_session(&CreateSessionRequest::new("hello"));
+        
+        rate_limited.assert();
+        success.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_idempotent_create_session_is_retried() {
+        let mut server = mockito::Server::new();
+        let outage = server.mock("POST", "/v1/sessions")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let existing = server.mock("POST", "/v1/sessions")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "prompt": "hello",
+                "idempotent": true,
+                "title": "Greeting",
+                "max_acu_limit": 5,
+                "unlisted": true,
+                "knowledge_ids": ["note-1"],
+                "secret_ids": ["secret-1"],
+            })))
+            .with_status(200)
+            .with_body(r#"{"session_id":"abc","url":"https://app.devin.ai/sessions/abc","is_new_session":false}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url())
+            .with_retry_policy(fast_retry_policy());
+        let request = CreateSessionRequest {
+            idempotent: true,
+            title: Some("Greeting".to_string()),
+            max_acu_limit: Some(5),
+            unlisted: true,
+            knowledge_ids: vec!["note-1".to_string()],
+            secret_ids: vec!["secret-1".to_string()],
+            ..CreateSessionRequest::new("hello")
+        };
+        let created = client.create_session(&request).unwrap();
+        
+        outage.assert();
+        existing.assert();
+        assert_eq!(created.url, "https://app.devin.ai/sessions/abc");
+        assert!(!created.is_new_session);
+    }
+    
+    #[test]
+    fn test_terminate_session() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("DELETE", "/v1/sessions/abc")
+            .match_header("authorization", "Bearer test-token")
+            .with_status(200)
+            .with_body(r#"{"detail":"Session terminated"}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert!(client.terminate_session("abc").is_ok());
+        mock.assert();
+    }
+    
+    #[test]
+    fn test_update_session_tags() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("PUT", "/v1/sessions/abc/tags")
+            .match_body(mockito::Matcher::Json(serde_json::json!({"tags": ["ci", "backend"]})))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let tags = vec!["ci".to_string(), "backend".to_string()];
+        assert!(client.update_session_tags("abc", &tags).is_ok());
+        mock.assert();
+    }
+    
+    #[test]
+    fn test_session_url() {
+        let client = ApiClient::new_with_url("test-token", "https://api.devin.ai");
+        assert_eq!(client.session_url("devin-abc123"), "https://app.devin.ai/sessions/abc123");
+        
+        let client = ApiClient::new_with_url("test-token", "https://devin.internal.example.com/api/");
+        assert_eq!(client.session_url("abc"), "https://devin.internal.example.com/sessions/abc");
+    }
+    
+    #[test]
+    fn test_session_details_get_a_url() {
+        let mut server = mockito::Server::new();
+        let _derived = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(SESSION_JSON)
+            .create();
+        let _returned = server.mock("GET", "/v1/sessions/def")
+            .with_status(200)
+            .with_body(r#"{"session_id":"def","status":"running","created_at":"2024-01-01T00:00:00Z","updated_at":null,
+                          "url":"https://app.devin.ai/sessions/def"}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let derived = client.get_session_details("abc").unwrap();
+        assert_eq!(derived.url, Some(format!("{}/sessions/abc", server.url())));
+        let returned = client.get_session_details("def").unwrap();
+        assert_eq!(returned.url.as_deref(), Some("https://app.devin.ai/sessions/def"));
+    }
+    
+    fn attachment() -> Attachment {
+        Attachment {
+            file_name: "build.log".to_string(),
+            mime_type: mime_guess::mime::TEXT_PLAIN,
+            contents: Arc::from(&b"error: linker failed"[..]),
+        }
+    }
+    
+    #[test]
+    fn test_upload_attachment() {
+        let mut server = mockito::Server::new();
+        let upload = server.mock("POST", "/v1/attachments")
+            .match_header("content-type", mockito::Matcher::Regex("multipart/form-data".to_string()))
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(r#"filename="build.log""#.to_string()),
+                mockito::Matcher::Regex("Content-Type: text/plain".to_string()),
+                mockito::Matcher::Regex("error: linker failed".to_string()),
+            ]))
+            .with_status(200)
+            .with_body(r#""https://files.devin.ai/attachments/123/build.log""#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let uploaded = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&uploaded);
+        let url = client.upload_attachment(&attachment(), Some(Arc::new(move |bytes| counter.store(bytes, Ordering::SeqCst))));
+        
+        upload.assert();
+        assert_eq!(url.unwrap(), "https://files.devin.ai/attachments/123/build.log");
+        assert_eq!(uploaded.load(Ordering::SeqCst), 20);
+    }
+    
+    #[test]
+    fn test_upload_attachment_accepts_an_object() {
+        let mut server = mockito::Server::new();
+        let _upload = server.mock("POST", "/v1/attachments")
+            .with_status(200)
+            .with_body(r#"{"url":"https://files.devin.ai/attachments/456/build.log"}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let url = client.upload_attachment(&attachment(), None).unwrap();
+        assert_eq!(url, "https://files.devin.ai/attachments/456/build.log");
+    }
+    
+    #[test]
+    fn test_upload_attachment_too_large() {
+        let mut server = mockito::Server::new();
+        let _upload = server.mock("POST", "/v1/attachments")
+            .with_status(413)
+            .with_body("Request Entity Too Large")
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert!(client.upload_attachment(&attachment(), None).is_err());
+    }
+    
     #[test]
     fn test_make_request() {
         let token = "test-token";
@@ -1,2 +1,7 @@
 pub mod client;
 pub mod models;
+pub mod pagination;
+pub mod poll;
+pub mod retry;
+pub mod stream;
+pub mod upload;
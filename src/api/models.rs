@@ -1,14 +1,46 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CreateSessionRequest {
     pub prompt: String,
+    /// Return the existing session when the same request was already made
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playbook_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Most ACUs the session may use before it is stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_acu_limit: Option<u32>,
+    /// Keep the session out of the organization's session list
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unlisted: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub knowledge_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_ids: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CreateSessionRequest {
+    /// Request a session with nothing but a prompt
+    pub fn new(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CreateSessionResponse {
     pub session_id: String,
     pub url: String,
+    /// False when an idempotent request returned an existing session
     pub is_new_session: bool,
 }
 
@@ -23,15 +55,91 @@ pub struct MessageResponse {
     pub done: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMessage {
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub message: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+}
+
+impl SessionMessage {
+    /// Whether the message was written by Devin rather than the user
+    pub fn is_from_devin(&self) -> bool {
+        self.message_type == "devin_message"
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SessionDetails {
     pub session_id: String,
     pub status: String,
     pub created_at: String,
     pub updated_at: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub messages: Vec<SessionMessage>,
+    /// Web app address; filled in by `ApiClient` when the API leaves it out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Coarse classification of a session's `status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// Devin is still working (or about to)
+    Running,
+    /// Devin is waiting for input from the user
+    Blocked,
+    /// Devin finished the task
+    Finished,
+    /// The session ended without finishing (stopped, expired or failed)
+    Failed,
+}
+
+impl SessionDetails {
+    pub fn state(&self) -> SessionState {
+        match self.status.to_lowercase().as_str() {
+            "blocked" => SessionState::Blocked,
+            "finished" | "completed" | "succeeded" => SessionState::Finished,
+            "stopped" | "expired" | "failed" | "error" | "cancelled" | "terminated" => SessionState::Failed,
+            _ => SessionState::Running,
+        }
+    }
+    
+    /// The most recent message written by Devin
+    pub fn last_devin_message(&self) -> Option<&SessionMessage> {
+        self.messages.iter().rev().find(|message| message.is_from_devin())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTagsRequest {
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListSessionsResponse {
     pub sessions: Vec<SessionDetails>,
+    /// Cursor for the next page, for APIs that page with cursors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Result of uploading an attachment: the URL alone, or an object holding it
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum AttachmentResponse {
+    Url(String),
+    Object { url: String },
+}
+
+impl AttachmentResponse {
+    pub fn into_url(self) -> String {
+        match self {
+            AttachmentResponse::Url(url) | AttachmentResponse::Object { url } => url,
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use crate::api::client::{ApiClient, ApiError};
+use crate::api::models::SessionDetails;
+
+/// Number of sessions requested per page unless configured otherwise
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Position and size of a page of sessions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    /// Maximum number of sessions in the page
+    pub limit: usize,
+    /// Number of sessions to skip; ignored when `cursor` is set
+    pub offset: usize,
+    /// Opaque cursor returned by the previous page, if the API uses cursors
+    pub cursor: Option<String>,
+}
+
+impl PageRequest {
+    /// The first page with the given size
+    pub fn first(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            offset: 0,
+            cursor: None,
+        }
+    }
+}
+
+/// Lazily iterates over all sessions, fetching one page at a time
+///
+/// The API is paged with `next_cursor` when it returns one, and with
+/// `limit`/`offset` otherwise. Iteration ends after a short or empty page, a
+/// page without a cursor when cursors are in use, a page repeating the one
+/// before it, or the first error.
+pub struct SessionPages<'a> {
+    client: &'a ApiClient,
+    next_page: Option<PageRequest>,
+    buffer: std::vec::IntoIter<SessionDetails>,
+    /// Session IDs of the last page, to notice a server ignoring the offset
+    previous_ids: Vec<String>,
+}
+
+impl<'a> SessionPages<'a> {
+    pub fn new(client: &'a ApiClient, page_size: usize) -> Self {
+        Self {
+            client,
+            next_page: Some(PageRequest::first(page_size)),
+            buffer: Vec::new().into_iter(),
+            previous_ids: Vec::new(),
+        }
+    }
+}
+
+impl Iterator for SessionPages<'_> {
+    type Item = Result<SessionDetails, ApiError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(session) = self.buffer.next() {
+                return Some(Ok(session));
+            }
+            
+            let request = self.next_page.take()?;
+            let page = match self.client.list_sessions_page(&request) {
+                Ok(page) => page,
+                Err(e) => return Some(Err(e)),
+            };
+            
+            // A server that ignores the offset or cursor sends the same page forever
+            let ids: Vec<String> = page.sessions.iter().map(|session| session.session_id.clone()).collect();
+            if ids.is_empty() || ids == self.previous_ids {
+                return None;
+            }
+            self.previous_ids = ids;
+            
+            let count = page.sessions.len();
+            self.next_page = match page.next_cursor {
+                Some(cursor) => Some(PageRequest {
+                    offset: request.offset + count,
+                    cursor: Some(cursor),
+                    ..request
+                }),
+                // A full page without a cursor means there may be more at the next offset
+                None if request.cursor.is_none() && count == request.limit => Some(PageRequest {
+                    offset: request.offset + count,
+                    ..request
+                }),
+                _ => None,
+            };
+            self.buffer = page.sessions.into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    
+    fn page_json(ids: &[&str], next_cursor: Option<&str>) -> String {
+        let sessions: Vec<serde_json::Value> = ids.iter()
+            .map(|id| serde_json::json!({
+                "session_id": id,
+                "status": "running",
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": null,
+            }))
+            .collect();
+        serde_json::json!({"sessions": sessions, "next_cursor": next_cursor}).to_string()
+    }
+    
+    fn offset_query(limit: &str, offset: &str) -> Matcher {
+        Matcher::AllOf(vec![
+            Matcher::UrlEncoded("limit".into(), limit.into()),
+            Matcher::UrlEncoded("offset".into(), offset.into()),
+        ])
+    }
+    
+    fn ids(client: &ApiClient, page_size: usize) -> Vec<String> {
+        SessionPages::new(client, page_size)
+            .map(|session| session.unwrap().session_id)
+            .collect()
+    }
+    
+    #[test]
+    fn test_pages_by_offset_until_short_page() {
+        let mut server = mockito::Server::new();
+        let first = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "0"))
+            .with_status(200)
+            .with_body(page_json(&["a", "b"], None))
+            .create();
+        let second = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "2"))
+            .with_status(200)
+            .with_body(page_json(&["c"], None))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert_eq!(ids(&client, 2), vec!["a", "b", "c"]);
+        first.assert();
+        second.assert();
+    }
+    
+    #[test]
+    fn test_pages_by_cursor() {
+        let mut server = mockito::Server::new();
+        let first = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "0"))
+            .with_status(200)
+            .with_body(page_json(&["a", "b"], Some("page-2")))
+            .create();
+        let second = server.mock("GET", "/v1/sessions")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+                Matcher::UrlEncoded("cursor".into(), "page-2".into()),
+            ]))
+            .with_status(200)
+            .with_body(page_json(&["c", "d"], None))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert_eq!(ids(&client, 2), vec!["a", "b", "c", "d"]);
+        first.assert();
+        second.assert();
+    }
+    
+    #[test]
+    fn test_stops_when_offset_is_ignored() {
+        let mut server = mockito::Server::new();
+        let same_page = server.mock("GET", "/v1/sessions")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .with_body(page_json(&["a", "b"], None))
+            .expect(2)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert_eq!(ids(&client, 2), vec!["a", "b"]);
+        same_page.assert();
+    }
+    
+    #[test]
+    fn test_stops_at_empty_page() {
+        let mut server = mockito::Server::new();
+        let first = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "0"))
+            .with_status(200)
+            .with_body(page_json(&["a", "b"], Some("page-2")))
+            .create();
+        let empty = server.mock("GET", "/v1/sessions")
+            .match_query(Matcher::UrlEncoded("cursor".into(), "page-2".into()))
+            .with_status(200)
+            .with_body(page_json(&[], Some("page-3")))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        assert_eq!(ids(&client, 2), vec!["a", "b"]);
+        first.assert();
+        empty.assert();
+    }
+    
+    #[test]
+    fn test_fetches_pages_lazily() {
+        let mut server = mockito::Server::new();
+        let first = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "0"))
+            .with_status(200)
+            .with_body(page_json(&["a", "b"], None))
+            .create();
+        let second = server.mock("GET", "/v1/sessions")
+            .match_query(offset_query("2", "2"))
+            .with_status(200)
+            .with_body(page_json(&["c", "d"], None))
+            .expect(0)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let taken: Vec<_> = SessionPages::new(&client, 2).take(2).collect();
+        assert_eq!(taken.len(), 2);
+        first.assert();
+        second.assert();
+    }
+    
+    #[test]
+    fn test_stops_after_error() {
+        let mut server = mockito::Server::new();
+        let _unauthorized = server.mock("GET", "/v1/sessions")
+            .match_query(Matcher::Any)
+            .with_status(401)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let mut pages = SessionPages::new(&client, 2);
+        assert!(matches!(pages.next(), Some(Err(ApiError::Unauthorized { .. }))));
+        assert!(pages.next().is_none());
+    }
+}
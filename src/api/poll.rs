@@ -0,0 +1,165 @@
+use crate::api::client::{ApiClient, ApiError};
+use crate::api::models::SessionDetails;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often a cancellable sleep checks whether it was cancelled
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Repeated `get_session_details` calls for one session
+///
+/// Polls come every `poll_interval` while Devin is writing, and the delay
+/// doubles up to `max_poll_interval` while the session is quiet. Shared by
+/// `sessions wait` and the REPL's polled replies.
+pub struct SessionPoller {
+    client: ApiClient,
+    session_id: String,
+    poll_interval: Duration,
+    max_poll_interval: Duration,
+    interval: Duration,
+    polled: bool,
+    /// Messages in the session as of the previous poll
+    seen: usize,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl SessionPoller {
+    pub fn new(client: ApiClient, session_id: &str, poll_interval: Duration, max_poll_interval: Duration) -> Self {
+        Self {
+            client,
+            session_id: session_id.to_string(),
+            poll_interval,
+            max_poll_interval: max_poll_interval.max(poll_interval),
+            interval: poll_interval,
+            polled: false,
+            seen: 0,
+            cancelled: None,
+        }
+    }
+    
+    /// Change the delay between polls and how far it may back off
+    pub fn with_intervals(mut self, poll_interval: Duration, max_poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.max_poll_interval = max_poll_interval.max(poll_interval);
+        self.interval = poll_interval;
+        self
+    }
+    
+    /// Cut `sleep` short once `cancelled` is set
+    pub fn with_cancel(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+    
+    /// Fetch the session, along with the index of its first message that is new
+    /// since the previous poll
+    pub fn poll(&mut self) -> Result<(SessionDetails, usize), ApiError> {
+        let details = self.client.get_session_details(&self.session_id)?;
+        let first_new = self.seen.min(details.messages.len());
+        
+        // Poll quickly while Devin is writing, and back off while it is quiet
+        self.interval = if !self.polled || details.messages.len() > first_new {
+            self.poll_interval
+        } else {
+            (self.interval * 2).min(self.max_poll_interval)
+        };
+        self.polled = true;
+        self.seen = details.messages.len();
+        Ok((details, first_new))
+    }
+    
+    /// How long to wait before the next poll, nothing before the first one
+    pub fn delay(&self) -> Duration {
+        if self.polled {
+            self.interval
+        } else {
+            Duration::ZERO
+        }
+    }
+    
+    /// Sleep for `delay`, returning `false` early if the poller was cancelled
+    pub fn sleep(&self, delay: Duration) -> bool {
+        let Some(cancelled) = &self.cancelled else {
+            thread::sleep(delay);
+            return true;
+        };
+        
+        let mut remaining = delay;
+        while !cancelled.load(Ordering::SeqCst) {
+            if remaining.is_zero() {
+                return true;
+            }
+            let step = remaining.min(CANCEL_CHECK_INTERVAL);
+            thread::sleep(step);
+            remaining -= step;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    
+    fn session_json(replies: &[&str]) -> String {
+        let mut messages = vec![serde_json::json!({"type": "initial_user_message", "message": "Fix the build"})];
+        messages.extend(replies.iter().map(|reply| serde_json::json!({"type": "devin_message", "message": reply})));
+        serde_json::json!({
+            "session_id": "abc",
+            "status": "running",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": null,
+            "messages": messages,
+        }).to_string()
+    }
+    
+    #[test]
+    fn test_backs_off_while_quiet() {
+        let mut server = mockito::Server::new();
+        let _first = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json(&["Looking"]))
+            .expect(2)
+            .create();
+        let _second = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json(&["Looking", "Found it"]))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let mut poller = SessionPoller::new(client, "abc", Duration::from_secs(1), Duration::from_secs(3));
+        assert_eq!(poller.delay(), Duration::ZERO);
+        
+        let (details, first_new) = poller.poll().unwrap();
+        assert_eq!((details.messages.len(), first_new), (2, 0));
+        assert_eq!(poller.delay(), Duration::from_secs(1));
+        
+        let (_, first_new) = poller.poll().unwrap();
+        assert_eq!(first_new, 2);
+        assert_eq!(poller.delay(), Duration::from_secs(2));
+        
+        let (details, first_new) = poller.poll().unwrap();
+        assert_eq!(details.messages[first_new].message, "Found it");
+        assert_eq!(poller.delay(), Duration::from_secs(1));
+    }
+    
+    #[test]
+    fn test_sleep_stops_when_cancelled() {
+        let client = ApiClient::new_with_url("test-token", "http://127.0.0.1:9");
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let poller = SessionPoller::new(client, "abc", Duration::from_secs(60), Duration::from_secs(60))
+            .with_cancel(Arc::clone(&cancelled));
+        
+        let flag = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            flag.store(true, Ordering::SeqCst);
+        });
+        let started = Instant::now();
+        assert!(!poller.sleep(Duration::from_secs(60)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
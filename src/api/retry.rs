@@ -0,0 +1,165 @@
+use crate::api::client::ApiError;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Controls how `ApiClient` retries requests that fail transiently
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further attempt
+    pub base_delay: Duration,
+    /// Upper bound for a single delay, including one requested by `Retry-After`
+    pub max_delay: Duration,
+    /// Randomize delays so concurrent clients don't retry in lockstep
+    pub jitter: bool,
+    /// Also retry non-idempotent requests (such as `create_session`) after
+    /// failures where the server may already have processed them
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+    
+    /// Decide whether a failed attempt should be retried
+    ///
+    /// Idempotent requests are retried on rate limiting, gateway errors,
+    /// timeouts and dropped connections. Non-idempotent requests are only
+    /// retried when the server provably rejected them (429), unless
+    /// `retry_non_idempotent` is set.
+    pub fn should_retry(&self, error: &ApiError, attempt: u32, idempotent: bool) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+        
+        match error {
+            ApiError::RateLimited { .. } => true,
+            ApiError::Server { status: 500 | 502 | 503 | 504, .. } => {
+                idempotent || self.retry_non_idempotent
+            }
+            ApiError::Timeout(_) | ApiError::ConnectionError(_) => {
+                idempotent || self.retry_non_idempotent
+            }
+            _ => false,
+        }
+    }
+    
+    /// Delay to wait before the attempt following `attempt` (1-based)
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+        
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        
+        if self.jitter {
+            // Equal jitter: keep half of the backoff, randomize the other half
+            let half = backoff / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            backoff
+        }
+    }
+}
+
+/// A random number in `[0, 1)` without pulling in a RNG crate
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn policy_without_jitter() -> RetryPolicy {
+        RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+    
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = policy_without_jitter();
+        assert_eq!(policy.delay(1, None), Duration::from_millis(500));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(2000));
+        assert_eq!(policy.delay(30, None), Duration::from_secs(30));
+    }
+    
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for _ in 0..20 {
+            let delay = policy.delay(2, None);
+            assert!(delay >= Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1000));
+        }
+    }
+    
+    #[test]
+    fn test_retry_after_is_honored_and_capped() {
+        let policy = policy_without_jitter();
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(5))), Duration::from_secs(5));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(600))), Duration::from_secs(30));
+    }
+    
+    #[test]
+    fn test_should_retry_respects_idempotency() {
+        let policy = RetryPolicy::default();
+        let outage = ApiError::Server {
+            status: 503,
+            body: String::new(),
+            request_id: None,
+        };
+        let rate_limited = ApiError::RateLimited {
+            retry_after: None,
+            request_id: None,
+        };
+        
+        assert!(policy.should_retry(&outage, 1, true));
+        assert!(!policy.should_retry(&outage, 1, false));
+        assert!(policy.should_retry(&rate_limited, 1, false));
+        assert!(!policy.should_retry(&outage, 3, true));
+        
+        let opted_in = RetryPolicy {
+            retry_non_idempotent: true,
+            ..RetryPolicy::default()
+        };
+        assert!(opted_in.should_retry(&outage, 1, false));
+    }
+    
+    #[test]
+    fn test_should_not_retry_client_errors() {
+        let policy = RetryPolicy::default();
+        let not_found = ApiError::NotFound {
+            message: None,
+            request_id: None,
+        };
+        assert!(!policy.should_retry(&not_found, 1, true));
+        assert!(!RetryPolicy::none().should_retry(&ApiError::Timeout("slow".to_string()), 1, true));
+    }
+}
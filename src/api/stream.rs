@@ -0,0 +1,333 @@
+use crate::api::client::{ApiClient, ApiError};
+use crate::api::models::{MessageResponse, SessionState};
+use crate::api::poll::SessionPoller;
+use reqwest::blocking::Response;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Lines};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Delay between session polls when the API does not stream replies
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A piece of Devin's reply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageEvent {
+    /// Part of a message that arrived since the previous event
+    Text(String),
+    /// A complete message, ending with a newline
+    Message(String),
+    /// Devin has finished replying
+    Done,
+}
+
+enum Source {
+    /// Server-sent events, one `MessageResponse` per `data:` payload
+    Events(Box<Lines<BufReader<Response>>>),
+    /// Repeated `get_session_details` calls until the session stops running
+    Poll,
+}
+
+/// Incremental reply from Devin, fetched as it is consumed
+///
+/// Ends after `MessageEvent::Done` or the first error.
+pub struct MessageStream {
+    source: Source,
+    poller: SessionPoller,
+    /// Devin messages after the latest user message that were already yielded
+    seen: usize,
+    /// Text already yielded from the initial response, not to be repeated by polls
+    already_shown: Option<String>,
+    pending: VecDeque<MessageEvent>,
+    finished: bool,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl MessageStream {
+    /// Stream the reply to a message from the response to sending it
+    ///
+    /// An event stream is read as it arrives. A plain JSON `MessageResponse`
+    /// is yielded as is and, unless it is `done`, followed by session polls.
+    pub(crate) fn from_response(client: ApiClient, session_id: &str, response: Response) -> Result<Self, ApiError> {
+        let is_event_stream = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        
+        if is_event_stream {
+            return Ok(Self::new(client, session_id, Source::Events(Box::new(BufReader::new(response).lines()))));
+        }
+        
+        let reply: MessageResponse = response.json()
+            .map_err(|e| ApiError::ParseError(e.to_string()))?;
+        let mut stream = Self::new(client, session_id, Source::Poll);
+        stream.push_reply(reply, true);
+        Ok(stream)
+    }
+    
+    /// Follow a session's new replies by polling it until it stops running
+    pub fn follow(client: ApiClient, session_id: &str) -> Self {
+        Self::new(client, session_id, Source::Poll)
+    }
+    
+    fn new(client: ApiClient, session_id: &str, source: Source) -> Self {
+        Self {
+            source,
+            poller: SessionPoller::new(client, session_id, DEFAULT_POLL_INTERVAL, DEFAULT_POLL_INTERVAL),
+            seen: 0,
+            already_shown: None,
+            pending: VecDeque::new(),
+            finished: false,
+            cancelled: None,
+        }
+    }
+    
+    /// Stop at the next event, or during the wait for the next poll, once
+    /// `cancelled` is set
+    pub fn with_cancel(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.poller = self.poller.with_cancel(Arc::clone(&cancelled));
+        self.cancelled = Some(cancelled);
+        self
+    }
+    
+    /// Change how often the session is polled
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poller = self.poller.with_intervals(poll_interval, poll_interval);
+        self
+    }
+    
+    fn push_reply(&mut self, reply: MessageResponse, complete: bool) {
+        if !reply.message.is_empty() {
+            self.already_shown = Some(reply.message.clone());
+            let event = if complete {
+                MessageEvent::Message(format!("{}\n", reply.message))
+            } else {
+                MessageEvent::Text(reply.message)
+            };
+            self.pending.push_back(event);
+        }
+        if reply.done {
+            self.pending.push_back(MessageEvent::Done);
+        }
+    }
+    
+    /// Read the next complete event from the event stream
+    fn read_event(lines: &mut Lines<BufReader<Response>>) -> Result<Option<String>, ApiError> {
+        let mut data = Vec::new();
+        for line in lines {
+            let line = line.map_err(|e| ApiError::ConnectionError(e.to_string()))?;
+            if line.is_empty() {
+                if data.is_empty() {
+                    continue;
+                }
+                return Ok(Some(data.join("\n")));
+            }
+            if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        Ok((!data.is_empty()).then(|| data.join("\n")))
+    }
+    
+    fn fetch(&mut self) -> Result<(), ApiError> {
+        match &mut self.source {
+            Source::Events(lines) => {
+                match Self::read_event(lines)? {
+                    Some(data) if data == "[DONE]" => self.pending.push_back(MessageEvent::Done),
+                    Some(data) => {
+                        let reply: MessageResponse = serde_json::from_str(&data)
+                            .map_err(|e| ApiError::ParseError(e.to_string()))?;
+                        self.push_reply(reply, false);
+                    }
+                    // The server closed the stream without saying it was done
+                    None => self.pending.push_back(MessageEvent::Done),
+                }
+            }
+            Source::Poll => {
+                if !self.poller.sleep(self.poller.delay()) {
+                    return Ok(());
+                }
+                
+                let (details, _) = self.poller.poll()?;
+                let start = details.messages.iter()
+                    .rposition(|message| !message.is_from_devin())
+                    .map_or(0, |index| index + 1);
+                let replies = &details.messages[start..];
+                for reply in replies.iter().skip(self.seen) {
+                    if self.already_shown.as_deref() == Some(reply.message.as_str()) {
+                        self.already_shown = None;
+                        continue;
+                    }
+                    self.pending.push_back(MessageEvent::Message(format!("{}\n", reply.message)));
+                }
+                self.seen = self.seen.max(replies.len());
+                
+                if details.state() != SessionState::Running {
+                    self.pending.push_back(MessageEvent::Done);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for MessageStream {
+    type Item = Result<MessageEvent, ApiError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if self.cancelled.as_ref().is_some_and(|cancelled| cancelled.load(Ordering::SeqCst)) {
+                self.finished = true;
+                break;
+            }
+            if let Some(event) = self.pending.pop_front() {
+                if event == MessageEvent::Done {
+                    self.finished = true;
+                }
+                return Some(Ok(event));
+            }
+            if let Err(e) = self.fetch() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::client::ClientOptions;
+    
+    fn events(stream: MessageStream) -> Vec<MessageEvent> {
+        stream.with_poll_interval(Duration::from_millis(1))
+            .map(|event| event.unwrap())
+            .collect()
+    }
+    
+    fn text(value: &str) -> MessageEvent {
+        MessageEvent::Text(value.to_string())
+    }
+    
+    fn message(value: &str) -> MessageEvent {
+        MessageEvent::Message(value.to_string())
+    }
+    
+    fn session_json(status: &str, replies: &[&str]) -> String {
+        let mut messages = vec![serde_json::json!({"type": "user_message", "message": "Fix the build"})];
+        messages.extend(replies.iter().map(|reply| serde_json::json!({"type": "devin_message", "message": reply})));
+        serde_json::json!({
+            "session_id": "abc",
+            "status": status,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": null,
+            "messages": messages,
+        }).to_string()
+    }
+    
+    #[test]
+    fn test_streams_server_sent_events() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("POST", "/v1/sessions/abc/messages")
+            .match_header("accept", mockito::Matcher::Regex("text/event-stream".to_string()))
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(concat!(
+                "data: {\"message\":\"Looking\",\"done\":false}\n\n",
+                ": keep-alive\n\n",
+                "data: {\"message\":\" into it\",\"done\":false}\n\n",
+                "data: {\"message\":\"\",\"done\":true}\n\n",
+            ))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let stream = client.stream_message("abc", "Fix the build").unwrap();
+        assert_eq!(events(stream), vec![text("Looking"), text(" into it"), MessageEvent::Done]);
+    }
+    
+    #[test]
+    fn test_complete_json_reply() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("POST", "/v1/sessions/abc/messages")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"All done","done":true}"#)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let stream = client.stream_message("abc", "Fix the build").unwrap();
+        assert_eq!(events(stream), vec![message("All done\n"), MessageEvent::Done]);
+    }
+    
+    #[test]
+    fn test_polls_until_session_stops_running() {
+        let mut server = mockito::Server::new();
+        let _send = server.mock("POST", "/v1/sessions/abc/messages")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"message":"On it","done":false}"#)
+            .create();
+        let _running = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("running", &["On it"]))
+            .expect(1)
+            .create();
+        let _blocked = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("blocked", &["On it", "Which branch?"]))
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let stream = client.stream_message("abc", "Fix the build").unwrap();
+        assert_eq!(events(stream), vec![message("On it\n"), message("Which branch?\n"), MessageEvent::Done]);
+    }
+    
+    #[test]
+    fn test_stops_when_cancelled() {
+        let client = ApiClient::new_with_url("test-token", "http://127.0.0.1:9");
+        let cancelled = Arc::new(AtomicBool::new(true));
+        let mut stream = MessageStream::follow(client, "abc").with_cancel(cancelled);
+        assert!(stream.next().is_none());
+    }
+    
+    #[test]
+    fn test_stream_honors_configured_timeout() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("POST", "/v1/sessions/abc/messages")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_chunked_body(|writer| {
+                writer.write_all(b"data: {\"message\":\"Looking\",\"done\":false}\n\n")?;
+                writer.flush()?;
+                std::thread::sleep(Duration::from_secs(3));
+                Ok(())
+            })
+            .create();
+        
+        let options = ClientOptions {
+            api_url: Some(server.url()),
+            timeout: Some(Duration::from_millis(500)),
+            max_attempts: Some(1),
+            ..ClientOptions::default()
+        };
+        let client = ApiClient::with_options("test-token", &options).unwrap();
+        let mut stream = client.stream_message("abc", "Fix the build").unwrap();
+        assert_eq!(stream.next().unwrap().unwrap(), text("Looking"));
+        assert!(stream.next().unwrap().is_err());
+    }
+    
+    #[test]
+    fn test_stops_after_error() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions/abc")
+            .with_status(404)
+            .create();
+        
+        let client = ApiClient::new_with_url("test-token", &server.url());
+        let mut stream = MessageStream::follow(client, "abc");
+        assert!(matches!(stream.next(), Some(Err(ApiError::NotFound { .. }))));
+        assert!(stream.next().is_none());
+    }
+}
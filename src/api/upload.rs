@@ -0,0 +1,45 @@
+use mime_guess::Mime;
+use reqwest::blocking::multipart::Part;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+/// Called with the number of bytes uploaded so far
+pub type UploadProgress = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// A file to upload, ready to be sent
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub file_name: String,
+    pub mime_type: Mime,
+    pub contents: Arc<[u8]>,
+}
+
+/// Body of an upload that reports how much of it has been read
+struct ProgressReader {
+    contents: Cursor<Arc<[u8]>>,
+    progress: Option<UploadProgress>,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.contents.read(buf)?;
+        if let Some(progress) = &self.progress {
+            progress(self.contents.position());
+        }
+        Ok(read)
+    }
+}
+
+impl Attachment {
+    /// Multipart form part with the contents, reporting upload progress
+    pub(crate) fn part(&self, progress: Option<UploadProgress>) -> Part {
+        let body = ProgressReader {
+            contents: Cursor::new(Arc::clone(&self.contents)),
+            progress,
+        };
+        Part::reader_with_length(body, self.contents.len() as u64)
+            .file_name(self.file_name.clone())
+            .mime_str(self.mime_type.as_ref())
+            .expect("a parsed MIME type is valid")
+    }
+}
@@ -0,0 +1,262 @@
+use crate::api::client::{ApiClient, ClientOptions};
+use crate::commands::attach::{prompt_with_attachments, upload_attachments};
+use crate::commands::redact::Redactor;
+use crate::commands::session::SessionOptions;
+use crate::commands::wait::{print_waiting, wait_for_session, WaitOptions};
+use crate::config::{get_api_token, get_session_defaults};
+use crate::output::{print_value, OutputFormat};
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+
+/// Where the prompt comes from and whether to wait for the result
+pub struct AskOptions {
+    /// Prompt given on the command line; `-` reads from stdin
+    pub prompt: Option<String>,
+    /// File to read the prompt from
+    pub file: Option<PathBuf>,
+    /// Follow the session until it stops running, printing Devin's replies
+    pub wait: Option<WaitOptions>,
+    /// Print the session's web address instead of its ID
+    pub print_url_only: bool,
+    /// Options for the new session
+    pub session: SessionOptions,
+}
+
+pub fn execute(ask_options: &AskOptions, profile: Option<&str>, options: &ClientOptions, format: OutputFormat) -> Result<()> {
+    let token = match get_api_token(profile) {
+        Ok(token) => token,
+        Err(e) => {
+            if format.is_human() {
+                eprintln!("{} {}", "✗ API token not configured:".red(), e);
+                eprintln!("\nRun 'devin configure' to set up your API token.");
+            }
+            return Err(e);
+        }
+    };
+    
+    let prompt = read_prompt(ask_options)?;
+    let api_client = ApiClient::with_options(&token, options)?;
+    
+    run(&api_client, &prompt, ask_options, format)
+}
+
+/// Resolve the prompt from the argument, the file or stdin
+fn read_prompt(ask_options: &AskOptions) -> Result<String> {
+    let prompt = match (&ask_options.prompt, &ask_options.file) {
+        (Some(_), Some(_)) => return Err(anyhow!("Give the prompt either as an argument or with --file, not both")),
+        (Some(prompt), None) if prompt != "-" => prompt.clone(),
+        (None, Some(path)) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read prompt from {}", path.display()))?,
+        _ => {
+            let mut stdin = io::stdin();
+            if ask_options.prompt.is_none() && stdin.is_terminal() {
+                return Err(anyhow!("No prompt given. Pass it as an argument, with --file, or on stdin"));
+            }
+            let mut prompt = String::new();
+            stdin.read_to_string(&mut prompt).context("Failed to read prompt from stdin")?;
+            prompt
+        }
+    };
+    
+    let prompt = prompt.trim().to_string();
+    if prompt.is_empty() {
+        return Err(anyhow!("The prompt is empty"));
+    }
+    Ok(prompt)
+}
+
+fn run(api_client: &ApiClient, prompt: &str, ask_options: &AskOptions, format: OutputFormat) -> Result<()> {
+    let prompt = ask_options.session.initial_prompt(prompt, format)?;
+    // Checked before uploading, so that a blocked prompt uploads nothing
+    let Some(prompt) = Redactor::from_config()?.check(&prompt)? else {
+        return Err(anyhow!("Not sent"));
+    };
+    let urls = upload_attachments(api_client, &ask_options.session.attachments, format)?;
+    let prompt = prompt_with_attachments(&prompt, &urls);
+    let request = ask_options.session.request(&prompt, &get_session_defaults()?);
+    let created = api_client.create_session(&request)?;
+    
+    let Some(wait_options) = &ask_options.wait else {
+        if print_value(format, &created)? {
+            return Ok(());
+        }
+        if ask_options.print_url_only {
+            println!("{}", created.url);
+        } else {
+            // Only the ID goes to stdout, so scripts can capture it
+            println!("{}", created.session_id);
+            eprintln!("{} {}", "Open in the browser:".dimmed(), created.url);
+        }
+        return Ok(());
+    };
+    
+    if format.is_human() {
+        let action = if created.is_new_session { "✓ Created session" } else { "✓ Reusing existing session" };
+        eprintln!("{} {}", action.green(), created.session_id);
+        eprintln!("{} {}", "Open in the browser:".dimmed(), created.url);
+    }
+    print_waiting(&created.session_id, format);
+    
+    wait_for_session(api_client, &created.session_id, wait_options, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::wait::EXIT_SESSION_BLOCKED;
+    use crate::commands::ExitStatusError;
+    use crate::config::ENV_LOCK;
+    use std::env;
+    use std::io::Write;
+    use std::time::Duration;
+    use tempfile::{tempdir, NamedTempFile};
+    
+    const CREATED_JSON: &str = r#"{"session_id":"abc","url":"https://app.devin.ai/sessions/abc","is_new_session":true}"#;
+    
+    fn ask_options(wait: bool) -> AskOptions {
+        AskOptions {
+            prompt: Some("Fix the build".to_string()),
+            file: None,
+            wait: wait.then(|| WaitOptions {
+                timeout: Some(Duration::from_secs(5)),
+                poll_interval: Duration::from_millis(1),
+                max_poll_interval: Duration::from_millis(1),
+            }),
+            print_url_only: false,
+            session: SessionOptions::default(),
+        }
+    }
+    
+    fn session_json(status: &str) -> String {
+        format!(
+            r#"{{"session_id":"abc","status":"{}","created_at":"2024-01-01T00:00:00Z","updated_at":null,
+                "messages":[{{"type":"initial_user_message","message":"Fix the build"}},
+                            {{"type":"devin_message","message":"All done"}}]}}"#,
+            status
+        )
+    }
+    
+    /// Run with an empty config, so the developer's own settings, such as
+    /// their redaction mode, play no part and their config file is left alone
+    fn run_isolated(api_client: &ApiClient, options: &AskOptions, format: OutputFormat) -> Result<()> {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::remove_var("DEVIN_REDACTION");
+        let result = run(api_client, "Fix the build", options, format);
+        env::remove_var("CONFY_CONFIG_PATH");
+        result
+    }
+    
+    #[test]
+    fn test_read_prompt_from_argument_and_file() {
+        assert_eq!(read_prompt(&ask_options(false)).unwrap(), "Fix the build");
+        
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "  Prompt from a file\n").unwrap();
+        let from_file = AskOptions {
+            prompt: None,
+            file: Some(file.path().to_path_buf()),
+            ..ask_options(false)
+        };
+        assert_eq!(read_prompt(&from_file).unwrap(), "Prompt from a file");
+        
+        let both = AskOptions {
+            file: Some(file.path().to_path_buf()),
+            ..ask_options(false)
+        };
+        assert!(read_prompt(&both).is_err());
+    }
+    
+    #[test]
+    fn test_run_without_wait() {
+        let mut server = mockito::Server::new();
+        let create = server.mock("POST", "/v1/sessions")
+            .match_body(mockito::Matcher::PartialJsonString(r#"{"prompt":"Fix the build"}"#.to_string()))
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let result = run_isolated(&api_client, &ask_options(false), OutputFormat::Table);
+        
+        create.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_run_uploads_attachments() {
+        let mut server = mockito::Server::new();
+        let upload = server.mock("POST", "/v1/attachments")
+            .with_status(200)
+            .with_body(r#""https://files.devin.ai/a/build.log""#)
+            .create();
+        let create = server.mock("POST", "/v1/sessions")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"prompt":"Fix the build\n\nATTACHMENT:\"https://files.devin.ai/a/build.log\""}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        
+        let mut log = NamedTempFile::new().unwrap();
+        writeln!(log, "error: linker failed").unwrap();
+        let mut options = ask_options(false);
+        options.session.attachments = vec![log.path().to_path_buf()];
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let result = run_isolated(&api_client, &options, OutputFormat::Json);
+        
+        upload.assert();
+        create.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_run_waits_until_finished() {
+        let mut server = mockito::Server::new();
+        let _create = server.mock("POST", "/v1/sessions")
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        let running = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("running"))
+            .expect(2)
+            .create();
+        let finished = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("finished"))
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let result = run_isolated(&api_client, &ask_options(true), OutputFormat::Table);
+        
+        running.assert();
+        finished.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_run_reports_blocked_session() {
+        let mut server = mockito::Server::new();
+        let _create = server.mock("POST", "/v1/sessions")
+            .with_status(200)
+            .with_body(CREATED_JSON)
+            .create();
+        let _blocked = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("blocked"))
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let error = run_isolated(&api_client, &ask_options(true), OutputFormat::Json).unwrap_err();
+        
+        let exit_status = error.downcast_ref::<ExitStatusError>().unwrap();
+        assert_eq!(exit_status.code, EXIT_SESSION_BLOCKED);
+    }
+}
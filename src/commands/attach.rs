@@ -0,0 +1,297 @@
+use crate::api::client::ApiClient;
+use crate::api::upload::Attachment;
+use crate::output::OutputFormat;
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use mime_guess::{mime, Mime};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Largest attachment that is uploaded, after zipping directories
+pub const MAX_ATTACHMENT_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Most files zipped from an attached directory
+pub const MAX_DIRECTORY_FILES: usize = 1000;
+
+/// Directories left out when zipping an attached directory
+const SKIPPED_DIRECTORIES: &[&str] = &[".git", "node_modules", "target"];
+
+/// Read a file, or zip a directory, so that it can be uploaded
+///
+/// Size limits are checked here, before anything is uploaded.
+pub fn load_attachment(path: &Path) -> Result<Attachment> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("Cannot attach {}", path.display()))?;
+    let name = path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "attachment".to_string());
+    
+    if metadata.is_dir() {
+        let contents = zip_directory(path)?;
+        return Ok(Attachment {
+            file_name: format!("{}.zip", name),
+            mime_type: "application/zip".parse().expect("a valid MIME type"),
+            contents: contents.into(),
+        });
+    }
+    
+    check_size(path, metadata.len())?;
+    let contents = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Attachment {
+        mime_type: detect_mime_type(path, &contents),
+        file_name: name,
+        contents: contents.into(),
+    })
+}
+
+fn check_size(path: &Path, size: u64) -> Result<()> {
+    if size > MAX_ATTACHMENT_SIZE {
+        return Err(anyhow!(
+            "{} is {}, more than the {} an attachment may have",
+            path.display(), HumanBytes(size), HumanBytes(MAX_ATTACHMENT_SIZE)
+        ));
+    }
+    Ok(())
+}
+
+/// MIME type from the file extension, or from the contents when it is unknown
+fn detect_mime_type(path: &Path, contents: &[u8]) -> Mime {
+    mime_guess::from_path(path).first().unwrap_or_else(|| {
+        if std::str::from_utf8(contents).is_ok() {
+            mime::TEXT_PLAIN
+        } else {
+            mime::APPLICATION_OCTET_STREAM
+        }
+    })
+}
+
+/// Zip the files below a directory, leaving out version control and build output
+fn zip_directory(dir: &Path) -> Result<Vec<u8>> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+    if files.is_empty() {
+        return Err(anyhow!("{} holds no files to attach", dir.display()));
+    }
+    if files.len() > MAX_DIRECTORY_FILES {
+        return Err(anyhow!(
+            "{} holds more than {} files; attach a smaller directory",
+            dir.display(), MAX_DIRECTORY_FILES
+        ));
+    }
+    
+    let total: u64 = files.iter()
+        .map(|file| fs::metadata(file).map(|metadata| metadata.len()).unwrap_or(0))
+        .sum();
+    check_size(dir, total)?;
+    
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for file in &files {
+        let relative = file.strip_prefix(dir).unwrap_or(file);
+        let name = relative.components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let contents = fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(&contents)?;
+    }
+    let contents = zip.finish()?.into_inner();
+    check_size(dir, contents.len() as u64)?;
+    Ok(contents)
+}
+
+/// Collect the files below `dir`, returning `false` once there are more than
+/// `MAX_DIRECTORY_FILES`, so that a huge tree isn't walked only to be refused
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<bool> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !SKIPPED_DIRECTORIES.contains(&entry.file_name().to_string_lossy().as_ref())
+                && !collect_files(&path, files)?
+            {
+                return Ok(false);
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+            if files.len() > MAX_DIRECTORY_FILES {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn progress_bar(attachment: &Attachment, format: OutputFormat) -> ProgressBar {
+    if !format.is_human() {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::new(attachment.contents.len() as u64);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg} [{bar:30}] {bytes}/{total_bytes}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    bar.set_message(format!("Uploading {}", attachment.file_name));
+    bar
+}
+
+/// Upload files and directories, returning the URLs to reference them by
+///
+/// Every path is read first, so that a missing or oversized file is reported
+/// before anything is uploaded. Progress is shown on stderr.
+pub fn upload_attachments(api_client: &ApiClient, paths: &[PathBuf], format: OutputFormat) -> Result<Vec<String>> {
+    let attachments = paths.iter()
+        .map(|path| load_attachment(path))
+        .collect::<Result<Vec<_>>>()?;
+    
+    let mut urls = Vec::new();
+    for attachment in &attachments {
+        let bar = progress_bar(attachment, format);
+        let progress = bar.clone();
+        let result = api_client.upload_attachment(attachment, Some(Arc::new(move |bytes| progress.set_position(bytes))));
+        bar.finish_and_clear();
+        let url = result.with_context(|| format!("Failed to upload {}", attachment.file_name))?;
+        
+        if format.is_human() {
+            eprintln!(
+                "{} {} ({}, {})",
+                "✓ Attached".green(), attachment.file_name, attachment.mime_type, HumanBytes(attachment.contents.len() as u64)
+            );
+        }
+        urls.push(url);
+    }
+    Ok(urls)
+}
+
+/// Reference uploaded attachments at the end of a prompt
+pub fn prompt_with_attachments(prompt: &str, urls: &[String]) -> String {
+    let mut prompt = prompt.to_string();
+    if !urls.is_empty() {
+        prompt.push('\n');
+    }
+    for url in urls {
+        prompt.push_str(&format!("\nATTACHMENT:\"{}\"", url));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_load_file_detects_mime_type() {
+        let temp_dir = tempdir().unwrap();
+        let screenshot = temp_dir.path().join("screenshot.png");
+        fs::write(&screenshot, [0x89, b'P', b'N', b'G']).unwrap();
+        let notes = temp_dir.path().join("NOTES");
+        fs::write(&notes, "plain text without an extension").unwrap();
+        let dump = temp_dir.path().join("core");
+        fs::write(&dump, [0xff, 0xfe, 0x00, 0x01]).unwrap();
+        
+        assert_eq!(load_attachment(&screenshot).unwrap().mime_type, mime::IMAGE_PNG);
+        assert_eq!(load_attachment(&notes).unwrap().mime_type, mime::TEXT_PLAIN);
+        assert_eq!(load_attachment(&dump).unwrap().mime_type, mime::APPLICATION_OCTET_STREAM);
+        assert!(load_attachment(&temp_dir.path().join("missing.log")).is_err());
+    }
+    
+    #[test]
+    fn test_load_file_refuses_large_files() {
+        let temp_dir = tempdir().unwrap();
+        let large = temp_dir.path().join("huge.log");
+        let file = fs::File::create(&large).unwrap();
+        file.set_len(MAX_ATTACHMENT_SIZE + 1).unwrap();
+        
+        let error = load_attachment(&large).unwrap_err().to_string();
+        assert!(error.contains("more than the 50.00 MiB"), "{}", error);
+    }
+    
+    #[test]
+    fn test_load_directory_zips_it() {
+        let temp_dir = tempdir().unwrap();
+        let spec = temp_dir.path().join("spec");
+        fs::create_dir_all(spec.join("images")).unwrap();
+        fs::create_dir_all(spec.join(".git")).unwrap();
+        fs::write(spec.join("README.md"), "# Spec").unwrap();
+        fs::write(spec.join("images").join("flow.svg"), "<svg/>").unwrap();
+        fs::write(spec.join(".git").join("HEAD"), "ref: refs/heads/main").unwrap();
+        
+        let attachment = load_attachment(&spec).unwrap();
+        assert_eq!(attachment.file_name, "spec.zip");
+        assert_eq!(attachment.mime_type.as_ref(), "application/zip");
+        
+        let mut archive = zip::ZipArchive::new(Cursor::new(attachment.contents.to_vec())).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, vec!["README.md", "images/flow.svg"]);
+        let mut readme = String::new();
+        archive.by_name("README.md").unwrap().read_to_string(&mut readme).unwrap();
+        assert_eq!(readme, "# Spec");
+        
+        let empty = temp_dir.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+        assert!(load_attachment(&empty).unwrap_err().to_string().contains("no files"));
+    }
+    
+    #[test]
+    fn test_collect_files_stops_past_the_limit() {
+        let temp_dir = tempdir().unwrap();
+        for dir in ["a", "b", "c"] {
+            let dir = temp_dir.path().join(dir);
+            fs::create_dir(&dir).unwrap();
+            for i in 0..MAX_DIRECTORY_FILES / 2 + 100 {
+                fs::write(dir.join(format!("{}.txt", i)), "").unwrap();
+            }
+        }
+        
+        // Whichever directory is read first, the walk ends in the second one
+        let mut files = Vec::new();
+        assert!(!collect_files(temp_dir.path(), &mut files).unwrap());
+        assert_eq!(files.len(), MAX_DIRECTORY_FILES + 1);
+        assert!(load_attachment(temp_dir.path()).unwrap_err().to_string().contains("more than 1000 files"));
+    }
+    
+    #[test]
+    fn test_prompt_with_attachments() {
+        assert_eq!(prompt_with_attachments("Fix it", &[]), "Fix it");
+        let urls = vec!["https://files.devin.ai/a/build.log".to_string()];
+        assert_eq!(
+            prompt_with_attachments("Fix it", &urls),
+            "Fix it\n\nATTACHMENT:\"https://files.devin.ai/a/build.log\""
+        );
+    }
+    
+    #[test]
+    fn test_upload_attachments_checks_every_path_first() {
+        let mut server = mockito::Server::new();
+        let upload = server.mock("POST", "/v1/attachments")
+            .with_status(200)
+            .with_body(r#""https://files.devin.ai/a/build.log""#)
+            .expect(1)
+            .create();
+        
+        let temp_dir = tempdir().unwrap();
+        let log = temp_dir.path().join("build.log");
+        fs::write(&log, "error").unwrap();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let paths = vec![log.clone(), temp_dir.path().join("missing.log")];
+        assert!(upload_attachments(&api_client, &paths, OutputFormat::Json).is_err());
+        let urls = upload_attachments(&api_client, &[log], OutputFormat::Json).unwrap();
+        
+        // Only the second call uploaded anything
+        upload.assert();
+        assert_eq!(urls, vec!["https://files.devin.ai/a/build.log"]);
+    }
+}
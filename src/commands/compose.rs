@@ -0,0 +1,140 @@
+use crate::commands::open_in_editor;
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Starts and ends a message of several lines in the REPL
+pub const MULTILINE_DELIMITER: &str = "\"\"\"";
+
+/// Prompt shown for the following lines of a multi-line message
+pub const CONTINUATION_PROMPT: &str = "... ";
+
+/// A message typed over several lines, between two `"""` delimiters
+#[derive(Debug, Default, PartialEq)]
+pub struct MultilineDraft {
+    lines: Vec<String>,
+}
+
+impl MultilineDraft {
+    /// A draft when `line` opens one, keeping any text after the delimiter
+    pub fn start(line: &str) -> Option<Self> {
+        let rest = line.trim_start().strip_prefix(MULTILINE_DELIMITER)?;
+        let mut draft = Self::default();
+        if !rest.trim().is_empty() {
+            draft.lines.push(rest.to_string());
+        }
+        Some(draft)
+    }
+    
+    /// Add a line, returning whether it closed the draft
+    pub fn push(&mut self, line: &str) -> bool {
+        match line.trim_end().strip_suffix(MULTILINE_DELIMITER) {
+            Some(last) => {
+                if !last.trim().is_empty() {
+                    self.lines.push(last.to_string());
+                }
+                true
+            }
+            None => {
+                self.lines.push(line.to_string());
+                false
+            }
+        }
+    }
+    
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Write a message in `$VISUAL` or `$EDITOR`, starting from `initial`
+///
+/// Returns `None` when the saved message is empty. The temporary file is
+/// readable only by the current user and removed afterwards.
+pub fn compose_in_editor(initial: &str) -> Result<Option<String>> {
+    let path = draft_path();
+    create_private(&path, initial)?;
+    let edited = open_in_editor(&path)
+        .and_then(|()| fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display())));
+    let _ = fs::remove_file(&path);
+    
+    let message = edited?.trim_end().to_string();
+    Ok((!message.trim().is_empty()).then_some(message))
+}
+
+/// A new file name in the temporary directory; `.md` lets editors highlight it
+fn draft_path() -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or(0);
+    env::temp_dir().join(format!("devin-message-{}-{}.md", process::id(), nanos))
+}
+
+/// Create a file readable only by the current user, failing if it exists
+fn create_private(path: &Path, contents: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn collect(lines: &[&str]) -> Option<(String, usize)> {
+        let mut draft = MultilineDraft::start(lines[0])?;
+        for (index, line) in lines.iter().enumerate().skip(1) {
+            if draft.push(line) {
+                return Some((draft.text(), index));
+            }
+        }
+        None
+    }
+    
+    #[test]
+    fn test_multiline_draft() {
+        assert_eq!(MultilineDraft::start("Fix the build"), None);
+        assert_eq!(
+            collect(&["\"\"\"", "# Spec", "", "Add a --dry-run flag.", "\"\"\"", "ignored"]),
+            Some(("# Spec\n\nAdd a --dry-run flag.".to_string(), 4))
+        );
+        assert_eq!(
+            collect(&["\"\"\"Fix the build", "  keeping the cache\"\"\""]),
+            Some(("Fix the build\n  keeping the cache".to_string(), 1))
+        );
+        assert_eq!(collect(&["\"\"\"", "never closed"]), None);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_compose_in_editor() {
+        let _guard = crate::config::ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let saved = env::var_os("VISUAL");
+        
+        // The editor appends to the last prompt it was given
+        env::set_var("VISUAL", "printf '\\nwith the cache\\n\\n' >>");
+        let edited = compose_in_editor("Fix the build");
+        env::set_var("VISUAL", "truncate -s 0");
+        let emptied = compose_in_editor("Fix the build");
+        env::set_var("VISUAL", "false");
+        let failed = compose_in_editor("");
+        
+        match saved {
+            Some(value) => env::set_var("VISUAL", value),
+            None => env::remove_var("VISUAL"),
+        }
+        
+        assert_eq!(edited.unwrap().as_deref(), Some("Fix the build\nwith the cache"));
+        assert_eq!(emptied.unwrap(), None);
+        assert!(failed.unwrap_err().to_string().contains("exited with"));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::api::client::ClientOptions;
+use crate::commands::show::mask_token;
+use crate::commands::{confirm, open_in_editor};
+use crate::config::schema::{find_key, ValueKind};
+use crate::config::{
+    check_config_file, config_path, ensure_config_file, get_profile_name, resolve_config_value,
+    resolve_config_values, set_config_value, unset_config_value, ResolvedValue,
+};
+use crate::output::{print_value, OutputFormat, Table};
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::io::{self, IsTerminal};
+
+#[derive(Serialize)]
+struct ConfigEntry {
+    key: &'static str,
+    value: String,
+    source: String,
+}
+
+impl From<ResolvedValue> for ConfigEntry {
+    fn from(resolved: ResolvedValue) -> Self {
+        // Secrets kept elsewhere are shown as "(the system keyring)" and the like
+        let value = if resolved.key.kind == ValueKind::Secret && !resolved.value.starts_with('(') {
+            mask_token(&resolved.value)
+        } else {
+            resolved.value
+        };
+        Self {
+            key: resolved.key.name,
+            value,
+            source: resolved.source.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ConfigChange<'a> {
+    profile: String,
+    key: &'a str,
+    action: &'a str,
+    /// False when there was nothing to unset
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct ConfigPath {
+    path: String,
+}
+
+/// List every setting in effect for the profile in use and where it comes from
+pub fn list(profile: Option<&str>, overrides: &ClientOptions, format: OutputFormat) -> Result<()> {
+    let entries: Vec<ConfigEntry> = resolve_config_values(profile, overrides)?
+        .into_iter()
+        .map(ConfigEntry::from)
+        .collect();
+    
+    if print_value(format, &entries)? {
+        return Ok(());
+    }
+    let mut table = Table::new(&["KEY", "VALUE", "SOURCE"]);
+    for entry in entries {
+        table.add_row(vec![entry.key.to_string(), entry.value, entry.source]);
+    }
+    println!("Profile: {}\n", get_profile_name(profile)?);
+    println!("{}", table.render());
+    Ok(())
+}
+
+/// Print the value of one setting
+pub fn get(
+    key: &str,
+    show_source: bool,
+    profile: Option<&str>,
+    overrides: &ClientOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    let key = find_key(key)?;
+    let entry: ConfigEntry = resolve_config_value(profile, key, overrides)?
+        .ok_or_else(|| anyhow!("{} is not set", key.name))?
+        .into();
+    
+    if print_value(format, &entry)? {
+        return Ok(());
+    }
+    if show_source {
+        println!("{}\t{}", entry.value, entry.source.dimmed());
+    } else {
+        println!("{}", entry.value);
+    }
+    Ok(())
+}
+
+/// Change a setting of the profile in use
+pub fn set(key: &str, value: &str, profile: Option<&str>, format: OutputFormat) -> Result<()> {
+    set_config_value(profile, key, value)?;
+    print_change(key, "set", true, profile, format)
+}
+
+/// Remove a setting from the profile in use
+pub fn unset(key: &str, profile: Option<&str>, format: OutputFormat) -> Result<()> {
+    let changed = unset_config_value(profile, key)?;
+    print_change(key, "unset", changed, profile, format)
+}
+
+fn print_change(key: &str, action: &str, changed: bool, profile: Option<&str>, format: OutputFormat) -> Result<()> {
+    let change = ConfigChange {
+        profile: get_profile_name(profile)?,
+        key,
+        action,
+        changed,
+    };
+    if print_value(format, &change)? {
+        return Ok(());
+    }
+    if changed {
+        println!("{} {} {} in profile {}", "✓".green(), capitalize(action), key, change.profile);
+    } else {
+        println!("{} is not set in profile {}", key, change.profile);
+    }
+    Ok(())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Print the location of the config file
+pub fn path(format: OutputFormat) -> Result<()> {
+    let path = config_path()?;
+    if !print_value(format, &ConfigPath { path: path.display().to_string() })? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// Open the config file in an editor and check it afterwards
+///
+/// When the edited file does not match the schema, the problems are shown and
+/// the editor can be reopened.
+pub fn edit(format: OutputFormat) -> Result<()> {
+    let path = ensure_config_file()?;
+    loop {
+        open_in_editor(&path)?;
+        match check_config_file() {
+            Ok(()) => break,
+            Err(e) if io::stdin().is_terminal() => {
+                eprintln!("{} {}", "✗".red(), e);
+                if !confirm("Edit the file again?")? {
+                    return Err(e);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    
+    if !print_value(format, &ConfigPath { path: path.display().to_string() })? {
+        println!("{} {}", "✓ Saved".green(), path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ENV_LOCK;
+    use std::env;
+    use std::fs;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_set_get_unset() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::remove_var("DEVIN_API_URL");
+        
+        let set_url = set("api_url", "https://devin.example.com", None, OutputFormat::Json);
+        let set_timeout = set("timeout", "30", None, OutputFormat::Json);
+        let invalid = set("timeout", "soon", None, OutputFormat::Json);
+        let unknown = set("color", "red", None, OutputFormat::Json);
+        let managed = set("api_token", "secret", None, OutputFormat::Json);
+        let got = get("api_url", true, None, &ClientOptions::default(), OutputFormat::Json);
+        let set_team = set("timeout", "45", Some("team"), OutputFormat::Json);
+        let config = fs::read_to_string(temp_dir.path().join("config.toml")).unwrap();
+        let unset_timeout = unset("timeout", None, OutputFormat::Json);
+        let missing = get("timeout", false, None, &ClientOptions::default(), OutputFormat::Json);
+        let team_timeout = get("timeout", false, Some("team"), &ClientOptions::default(), OutputFormat::Json);
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(set_url.is_ok());
+        assert!(set_timeout.is_ok());
+        assert!(invalid.is_err());
+        assert!(unknown.unwrap_err().to_string().contains("Unknown config key"));
+        assert!(managed.unwrap_err().to_string().contains("devin configure"));
+        assert!(got.is_ok());
+        assert!(config.contains("timeout = 30"));
+        assert!(set_team.is_ok());
+        assert!(config.contains("[profiles.team]"));
+        assert!(unset_timeout.is_ok());
+        assert!(missing.unwrap_err().to_string().contains("not set"));
+        assert!(team_timeout.is_ok());
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_edit_checks_the_file() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::set_var("VISUAL", "printf '[profiles.default]\\ntimeout = \"soon\"\\n' >");
+        let invalid = edit(OutputFormat::Json);
+        env::set_var("VISUAL", "printf '[profiles.default]\\ntimeout = 30\\n' >");
+        let valid = edit(OutputFormat::Json);
+        
+        env::remove_var("VISUAL");
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(invalid.unwrap_err().to_string().contains("timeout"));
+        assert!(valid.is_ok());
+    }
+}
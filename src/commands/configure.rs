@@ -1,21 +1,146 @@
-use crate::config::save_api_token;
-use anyhow::Result;
+use crate::api::client::{ApiClient, ApiError, ClientOptions};
+use crate::commands::confirm;
+use crate::config::{get_profile_name, get_token_storage, store_api_token, CommandStore, SecretStore, TokenStorage};
+use crate::output::{print_value, OutputFormat};
+use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
+use serde::Serialize;
+use std::io::{self, IsTerminal, Read};
 
-pub fn execute(token: &str) -> Result<()> {
-    save_api_token(token)?;
-    println!("{}", "API token configured successfully".green());
+/// Where the token comes from and how it is stored
+#[derive(Debug, Default)]
+pub struct ConfigureOptions {
+    /// Token given on the command line
+    pub token: Option<String>,
+    /// Read the token from stdin instead of prompting for it
+    pub token_stdin: bool,
+    /// Backend to keep the token in
+    pub storage: Option<TokenStorage>,
+    /// Command printing the token, for the command storage
+    pub token_command: Option<String>,
+    /// Check the token against the API before saving it
+    pub verify: bool,
+    /// Replace an existing token without asking
+    pub force: bool,
+}
+
+#[derive(Serialize)]
+struct ConfigureOutput {
+    configured: bool,
+    token_storage: TokenStorage,
+    verified: bool,
+}
+
+/// Store the API token, or the command printing it, for the profile in use
+///
+/// Without a token argument the token is read from stdin with `--token-stdin`,
+/// or asked for with a hidden prompt.
+pub fn execute(
+    configure_options: &ConfigureOptions,
+    profile: Option<&str>,
+    options: &ClientOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    confirm_overwrite(profile, configure_options.force)?;
+    
+    let token = read_token(configure_options)?;
+    if configure_options.verify {
+        // With a token command, check the token it prints
+        let token = match (&token, &configure_options.token_command) {
+            (Some(token), _) => token.clone(),
+            (None, Some(command)) => CommandStore::new(command).get(&get_profile_name(profile)?)?
+                .ok_or_else(|| anyhow!("The token command printed nothing"))?,
+            (None, None) => return Err(anyhow!("No API token given")),
+        };
+        verify_token(&token, options)?;
+    }
+    
+    // A token command only makes sense with the command storage
+    let storage = configure_options.storage
+        .or(configure_options.token_command.as_ref().map(|_| TokenStorage::Command));
+    let token_storage = store_api_token(profile, token.as_deref(), storage, configure_options.token_command.as_deref())?;
+    
+    let output = ConfigureOutput {
+        configured: true,
+        token_storage,
+        verified: configure_options.verify,
+    };
+    if !print_value(format, &output)? {
+        println!("{}", "API token configured successfully".green());
+        println!("Token storage: {}", token_storage.describe());
+    }
     Ok(())
 }
 
+/// Make sure an existing token is only replaced on purpose
+fn confirm_overwrite(profile: Option<&str>, force: bool) -> Result<()> {
+    if force || get_token_storage(profile)?.is_none() {
+        return Ok(());
+    }
+    let profile = get_profile_name(profile)?;
+    if !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "Profile '{}' already has an API token. Pass --force to replace it.",
+            profile
+        ));
+    }
+    if !confirm(&format!("Profile '{}' already has an API token. Replace it?", profile))? {
+        return Err(anyhow!("Kept the existing API token"));
+    }
+    Ok(())
+}
+
+/// Resolve the token from the argument, stdin or a hidden prompt
+///
+/// Returns `None` when the token is printed by a token command instead.
+fn read_token(configure_options: &ConfigureOptions) -> Result<Option<String>> {
+    let token = match (&configure_options.token, configure_options.token_stdin) {
+        (Some(_), true) => return Err(anyhow!("Give the token either as an argument or with --token-stdin, not both")),
+        (Some(token), false) => token.clone(),
+        (None, true) => {
+            let mut token = String::new();
+            io::stdin().read_to_string(&mut token).context("Failed to read the API token from stdin")?;
+            token
+        }
+        (None, false) if configure_options.token_command.is_some() => return Ok(None),
+        (None, false) => {
+            if !io::stdin().is_terminal() {
+                return Err(anyhow!("No API token given. Pipe it in with --token-stdin"));
+            }
+            rpassword::prompt_password("API token: ").context("Failed to read the API token")?
+        }
+    };
+    
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        return Err(anyhow!("The API token is empty"));
+    }
+    Ok(Some(token))
+}
+
+/// Check that the API accepts the token
+fn verify_token(token: &str, options: &ClientOptions) -> Result<()> {
+    let api_client = ApiClient::with_options(token, options)?;
+    match api_client.check_connection() {
+        Ok(()) => Ok(()),
+        Err(e @ ApiError::Unauthorized { .. }) => Err(anyhow::Error::new(e)
+            .context("The API rejected the token; it was not saved")),
+        Err(e) => Err(anyhow::Error::new(e)
+            .context("Could not verify the API token; pass --no-verify to save it anyway")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ENV_LOCK;
     use std::env;
     use tempfile::tempdir;
     
     #[test]
     fn test_execute() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
         // Create a temporary directory for the config file
         let temp_dir = tempdir().unwrap();
         let config_path = temp_dir.path().to_str().unwrap();
@@ -24,8 +149,12 @@ mod tests {
         env::set_var("CONFY_CONFIG_PATH", config_path);
         
         // Execute the command
-        let token = "test-token-123";
-        let result = execute(token);
+        let configure_options = ConfigureOptions {
+            token: Some("test-token-123".to_string()),
+            storage: Some(TokenStorage::Plaintext),
+            ..ConfigureOptions::default()
+        };
+        let result = execute(&configure_options, None, &ClientOptions::default(), OutputFormat::Table);
         
         // Clean up
         env::remove_var("CONFY_CONFIG_PATH");
@@ -33,4 +162,105 @@ mod tests {
         // Check the result
         assert!(result.is_ok());
     }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_execute_with_token_command() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let with_command = |command: &str| ConfigureOptions {
+            token_command: Some(command.to_string()),
+            force: true,
+            ..ConfigureOptions::default()
+        };
+        let stored = execute(&with_command("echo helper-token"), None, &ClientOptions::default(), OutputFormat::Json);
+        let token = crate::config::get_api_token(None);
+        let config = std::fs::read_to_string(temp_dir.path().join("config.toml")).unwrap();
+        let failing = execute(&with_command("exit 1"), None, &ClientOptions::default(), OutputFormat::Json);
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(stored.is_ok());
+        assert_eq!(token.unwrap(), "helper-token");
+        assert!(config.contains("token_storage = 'command'"));
+        assert!(failing.is_err());
+    }
+    
+    #[test]
+    fn test_keeps_existing_token_without_force() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let configure_options = |token: &str, force: bool| ConfigureOptions {
+            token: Some(token.to_string()),
+            storage: Some(TokenStorage::Plaintext),
+            force,
+            ..ConfigureOptions::default()
+        };
+        let first = execute(&configure_options("first-token", false), None, &ClientOptions::default(), OutputFormat::Json);
+        // Tests run without a terminal, so there is no one to confirm
+        let refused = execute(&configure_options("second-token", false), None, &ClientOptions::default(), OutputFormat::Json);
+        let kept = crate::config::get_api_token(None);
+        let forced = execute(&configure_options("third-token", true), None, &ClientOptions::default(), OutputFormat::Json);
+        let replaced = crate::config::get_api_token(None);
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(first.is_ok());
+        assert!(refused.unwrap_err().to_string().contains("--force"));
+        assert_eq!(kept.unwrap(), "first-token");
+        assert!(forced.is_ok());
+        assert_eq!(replaced.unwrap(), "third-token");
+    }
+    
+    #[test]
+    fn test_read_token() {
+        let from_argument = ConfigureOptions {
+            token: Some("  token-123\n".to_string()),
+            ..ConfigureOptions::default()
+        };
+        assert_eq!(read_token(&from_argument).unwrap().as_deref(), Some("token-123"));
+        
+        let both = ConfigureOptions {
+            token_stdin: true,
+            ..from_argument
+        };
+        assert!(read_token(&both).is_err());
+        
+        let from_command = ConfigureOptions {
+            token_command: Some("pass show devin".to_string()),
+            ..ConfigureOptions::default()
+        };
+        assert_eq!(read_token(&from_command).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_verify_token() {
+        let mut server = mockito::Server::new();
+        let _valid = server.mock("GET", "/v1/sessions")
+            .match_header("authorization", "Bearer valid-token")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(r#"{"sessions":[]}"#)
+            .create();
+        let _revoked = server.mock("GET", "/v1/sessions")
+            .match_header("authorization", "Bearer revoked-token")
+            .match_query(mockito::Matcher::Any)
+            .with_status(401)
+            .create();
+        
+        let options = ClientOptions {
+            api_url: Some(server.url()),
+            ..ClientOptions::default()
+        };
+        assert!(verify_token("valid-token", &options).is_ok());
+        let error = verify_token("revoked-token", &options).unwrap_err();
+        assert!(error.to_string().contains("rejected"));
+        assert!(matches!(error.downcast_ref::<ApiError>(), Some(ApiError::Unauthorized { .. })));
+    }
 }
@@ -1,55 +1,191 @@
-use crate::config::get_api_token;
-use crate::api::client::ApiClient;
+use crate::config::{get_api_token, get_token_storage, TokenStorage};
+use crate::api::client::{ApiClient, ApiError, ClientOptions};
+use crate::output::{print_value, OutputFormat};
 use anyhow::Result;
 use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute() -> Result<()> {
+/// Result of the checks, emitted for the JSON and YAML formats
+#[derive(Debug, Default, Serialize)]
+struct DoctorReport {
+    token_configured: bool,
+    token_storage: Option<TokenStorage>,
+    api_url: Option<String>,
+    connected: bool,
+    problem: Option<Problem>,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Problem {
+    kind: String,
+    diagnosis: String,
+    hint: String,
+    message: String,
+}
+
+pub fn execute(profile: Option<&str>, options: &ClientOptions, format: OutputFormat) -> Result<()> {
+    let mut report = DoctorReport::default();
+    let result = run_checks(profile, options, format, &mut report);
+    print_value(format, &report)?;
+    result
+}
+
+fn run_checks(
+    profile: Option<&str>,
+    options: &ClientOptions,
+    format: OutputFormat,
+    report: &mut DoctorReport,
+) -> Result<()> {
     // Check if API token is configured
-    let token_result = get_api_token();
+    let token_result = get_api_token(profile);
     
     match token_result {
         Ok(token) => {
-            println!("{}", "✓ API token is configured".green());
-            
-            println!("\nChecking API connectivity...");
+            report.token_configured = true;
+            if format.is_human() {
+                println!("{}", "✓ API token is configured".green());
+            }
+            check_token_storage(profile, format, report)?;
             
             // Create API client
-            let api_client = ApiClient::new(&token);
-            println!("{}", "✓ API client created successfully".green());
-            
-            // Check API connection
-            match api_client.check_connection() {
-                Ok(_) => {
-                    println!("{}", "✓ Connected to Devin API successfully".green());
-                }
+            let api_client = match ApiClient::with_options(&token, options) {
+                Ok(api_client) => api_client,
                 Err(e) => {
-                    println!("{} {}", "✗ Failed to connect to Devin API:".red(), e);
+                    let hint = "Check the api_url, proxy and ca_cert settings in your config file or environment.";
+                    if format.is_human() {
+                        println!("{} {}", "✗ Failed to create API client:".red(), e);
+                        println!("\n{}", hint);
+                    }
+                    report.problem = Some(Problem {
+                        kind: e.kind().to_string(),
+                        diagnosis: "Failed to create API client".to_string(),
+                        hint: hint.to_string(),
+                        message: e.to_string(),
+                    });
                     return Err(e.into());
                 }
+            };
+            report.api_url = Some(api_client.get_api_url().to_string());
+            if format.is_human() {
+                println!("{}", "✓ API client created successfully".green());
+                println!("\nChecking API connectivity ({})...", api_client.get_api_url());
             }
+            
+            check_api(&api_client, format, report)?;
         }
         Err(e) => {
-            println!("{} {}", "✗ API token not configured:".red(), e);
-            println!("\nRun 'devin configure <token>' to set up your API token.");
-            return Err(e.into());
+            let hint = "Run 'devin configure' to set up your API token.";
+            if format.is_human() {
+                println!("{} {}", "✗ API token not configured:".red(), e);
+                println!("\n{}", hint);
+            }
+            report.problem = Some(Problem {
+                kind: "token".to_string(),
+                diagnosis: "API token not configured".to_string(),
+                hint: hint.to_string(),
+                message: e.to_string(),
+            });
+            return Err(e);
         }
     }
     
     Ok(())
 }
 
+/// Warn about API tokens kept in plaintext
+fn check_token_storage(profile: Option<&str>, format: OutputFormat, report: &mut DoctorReport) -> Result<()> {
+    report.token_storage = get_token_storage(profile)?;
+    if report.token_storage == Some(TokenStorage::Plaintext) {
+        let warning = "API token is stored in plaintext in the config file; \
+                       run 'devin configure --storage keyring --force' to move it to the system keyring";
+        if format.is_human() {
+            println!("{} {}", "! Warning:".yellow(), warning);
+        }
+        report.warnings.push(warning.to_string());
+    }
+    Ok(())
+}
+
+/// Probe the API and print a diagnosis for the result
+fn check_api(api_client: &ApiClient, format: OutputFormat, report: &mut DoctorReport) -> Result<()> {
+    match api_client.check_connection() {
+        Ok(_) => {
+            report.connected = true;
+            if format.is_human() {
+                println!("{}", "✓ Connected to Devin API successfully".green());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            let (diagnosis, hint) = diagnose(&e);
+            if format.is_human() {
+                println!("{} {}", format!("✗ {}:", diagnosis).red(), e);
+                println!("\n{}", hint);
+            }
+            report.problem = Some(Problem {
+                kind: e.kind().to_string(),
+                diagnosis: diagnosis.to_string(),
+                hint: hint.to_string(),
+                message: e.to_string(),
+            });
+            Err(e.into())
+        }
+    }
+}
+
+/// Map a connection failure to a short diagnosis and a remediation hint
+fn diagnose(error: &ApiError) -> (&'static str, &'static str) {
+    match error {
+        ApiError::DnsError(_) => (
+            "Could not resolve the Devin API host",
+            "Check your network connection and DNS settings.",
+        ),
+        ApiError::TlsError(_) => (
+            "Could not establish a secure connection",
+            "Check that your system clock is correct and that no proxy is intercepting HTTPS traffic.",
+        ),
+        ApiError::Timeout(_) => (
+            "The Devin API did not respond in time",
+            "Check your network connection, or try again later.",
+        ),
+        ApiError::Unauthorized { .. } => (
+            "The API token is invalid or has been revoked",
+            "Run 'devin configure --force' with a valid API token.",
+        ),
+        ApiError::Server { .. } => (
+            "The Devin API is currently unavailable",
+            "This is a problem on the Devin side. Try again in a few minutes.",
+        ),
+        ApiError::RateLimited { .. } => (
+            "The Devin API is rate limiting this token",
+            "Wait a moment before running 'devin doctor' again.",
+        ),
+        _ => (
+            "Failed to connect to Devin API",
+            "Check your network connection and try again.",
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ENV_LOCK;
     use std::env;
     
     #[test]
+    #[ignore]
     fn test_execute() {
+        // This test is skipped because it needs network access to the real API
+        // The connection checks are tested against a mock server below
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
         // Set the environment variable for testing
         env::set_var("DEVIN_API_TOKEN", "test-token-123");
         
         // Execute the command
-        let result = execute();
+        let result = execute(None, &ClientOptions::default(), OutputFormat::Table);
         
         // Clean up
         env::remove_var("DEVIN_API_TOKEN");
@@ -58,6 +194,77 @@ mod tests {
         assert!(result.is_ok());
     }
     
+    #[test]
+    fn test_warns_about_plaintext_token() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempfile::tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        let mut before = DoctorReport::default();
+        let unconfigured = check_token_storage(None, OutputFormat::Json, &mut before);
+        crate::config::store_api_token(None, Some("test-token"), Some(TokenStorage::Plaintext), None).unwrap();
+        let mut after = DoctorReport::default();
+        let plaintext = check_token_storage(None, OutputFormat::Json, &mut after);
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(unconfigured.is_ok());
+        assert!(before.warnings.is_empty());
+        assert!(plaintext.is_ok());
+        assert_eq!(after.token_storage, Some(TokenStorage::Plaintext));
+        assert!(after.warnings[0].contains("plaintext"));
+    }
+    
+    #[test]
+    fn test_check_api_success() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body(r#"{"sessions":[]}"#)
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token-123", &server.url());
+        let mut report = DoctorReport::default();
+        assert!(check_api(&api_client, OutputFormat::Table, &mut report).is_ok());
+        assert!(report.connected);
+    }
+    
+    #[test]
+    fn test_check_api_invalid_token() {
+        let mut server = mockito::Server::new();
+        let _mock = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(403)
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token-123", &server.url());
+        let mut report = DoctorReport::default();
+        assert!(check_api(&api_client, OutputFormat::Json, &mut report).is_err());
+        assert!(!report.connected);
+        assert_eq!(report.problem.unwrap().kind, "unauthorized");
+    }
+    
+    #[test]
+    fn test_diagnose() {
+        let (diagnosis, hint) = diagnose(&ApiError::Unauthorized {
+            status: 401,
+            message: None,
+            request_id: None,
+        });
+        assert!(diagnosis.contains("invalid"));
+        assert!(hint.contains("devin configure"));
+        
+        let (diagnosis, _) = diagnose(&ApiError::Server {
+            status: 502,
+            body: String::new(),
+            request_id: None,
+        });
+        assert!(diagnosis.contains("unavailable"));
+        
+        let (diagnosis, _) = diagnose(&ApiError::DnsError("dns error".to_string()));
+        assert!(diagnosis.contains("resolve"));
+    }
+    
     #[test]
     #[ignore]
     fn test_execute_with_no_token() {
@@ -1,4 +1,111 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+use thiserror::Error;
+
+pub mod ask;
+pub mod attach;
+pub mod compose;
+pub mod config;
 pub mod configure;
 pub mod show;
 pub mod doctor;
+pub mod git_context;
+pub mod profile;
+pub mod redact;
+pub mod repl_helper;
 pub mod session;
+pub mod sessions;
+pub mod transcript;
+pub mod wait;
+
+/// A command failure that should end the process with a specific exit code
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct ExitStatusError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl ExitStatusError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Ask a yes/no question on the terminal; anything but "y" or "yes" is a no
+pub fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).context("Failed to read the answer")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Open a file in `$VISUAL` or `$EDITOR` and wait for the editor to exit
+pub fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    
+    // The editor may come with arguments, such as `code --wait`
+    let status = if cfg!(windows) {
+        Command::new("cmd").arg("/C").arg(format!("{} \"{}\"", editor, path.display())).status()
+    } else {
+        Command::new("sh").arg("-c").arg(format!("{} \"$1\"", editor)).arg("sh").arg(path).status()
+    }
+    .with_context(|| format!("Failed to start editor '{}'", editor))?;
+    
+    if !status.success() {
+        return Err(anyhow::anyhow!("Editor '{}' exited with {}", editor, status));
+    }
+    Ok(())
+}
+
+/// Open a URL in `$BROWSER`, or else in the desktop's default browser
+///
+/// Like other tools reading `$BROWSER`, several browsers may be given
+/// separated by `:`; the first one that starts successfully is used.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let browsers: Vec<String> = env::var("BROWSER")
+        .unwrap_or_default()
+        .split(':')
+        .map(str::trim)
+        .filter(|browser| !browser.is_empty())
+        .map(str::to_string)
+        .collect();
+    
+    let mut commands: Vec<Command> = browsers.iter()
+        .map(|browser| {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(format!("{} \"$1\"", browser)).arg("sh").arg(url);
+            command
+        })
+        .collect();
+    if commands.is_empty() {
+        let mut command = if cfg!(target_os = "macos") {
+            Command::new("open")
+        } else if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        } else {
+            Command::new("xdg-open")
+        };
+        command.arg(url);
+        commands.push(command);
+    }
+    
+    for mut command in commands {
+        if command.status().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    Err(anyhow::anyhow!("Could not open a browser; set $BROWSER or open {} yourself", url))
+}
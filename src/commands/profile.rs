@@ -0,0 +1,109 @@
+use crate::api::client::DEFAULT_API_URL;
+use crate::commands::show::mask_token;
+use crate::config::{list_profiles, remove_profile, use_profile, TokenStorage};
+use crate::output::{print_value, OutputFormat, Table};
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ProfileOutput {
+    name: String,
+    active: bool,
+    api_url: String,
+    api_token: String,
+    token_storage: TokenStorage,
+}
+
+#[derive(Serialize)]
+struct ProfileChange<'a> {
+    profile: &'a str,
+    action: &'a str,
+}
+
+/// List all profiles, marking the one in use
+pub fn list(profile: Option<&str>, format: OutputFormat) -> Result<()> {
+    let (active, profiles) = list_profiles(profile)?;
+    let profiles: Vec<ProfileOutput> = profiles.into_iter()
+        .map(|(name, profile)| ProfileOutput {
+            active: name == active,
+            token_storage: profile.storage(),
+            name,
+            api_url: profile.api_url.unwrap_or_else(|| DEFAULT_API_URL.to_string()),
+            api_token: mask_token(&profile.api_token),
+        })
+        .collect();
+    
+    if print_value(format, &profiles)? {
+        return Ok(());
+    }
+    if profiles.is_empty() {
+        println!("No profiles configured.");
+        println!("\nRun 'devin configure' to set up your API token.");
+        return Ok(());
+    }
+    
+    let mut table = Table::new(&["", "PROFILE", "API URL", "TOKEN"]);
+    for profile in profiles {
+        table.add_row(vec![
+            if profile.active { "*".to_string() } else { String::new() },
+            profile.name,
+            profile.api_url,
+            match profile.token_storage {
+                TokenStorage::Plaintext => profile.api_token,
+                storage => format!("({})", storage.describe()),
+            },
+        ]);
+    }
+    println!("{}", table.render());
+    Ok(())
+}
+
+/// Make a profile the one used by default
+pub fn use_(name: &str, format: OutputFormat) -> Result<()> {
+    use_profile(name)?;
+    if !print_value(format, &ProfileChange { profile: name, action: "used" })? {
+        println!("{} {}", "✓ Now using profile".green(), name);
+    }
+    Ok(())
+}
+
+/// Delete a profile
+pub fn remove(name: &str, format: OutputFormat) -> Result<()> {
+    remove_profile(name)?;
+    if !print_value(format, &ProfileChange { profile: name, action: "removed" })? {
+        println!("{} {}", "✓ Removed profile".green(), name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{save_api_token, ENV_LOCK};
+    use std::env;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_use_and_remove() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::set_var("DEVIN_TOKEN_STORAGE", "plaintext");
+        save_api_token(Some("team"), "team-token").unwrap();
+        env::remove_var("DEVIN_TOKEN_STORAGE");
+        
+        let listed = list(None, OutputFormat::Json);
+        let used = use_("team", OutputFormat::Json);
+        let removed = remove("team", OutputFormat::Json);
+        let missing = use_("team", OutputFormat::Json);
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(listed.is_ok());
+        assert!(used.is_ok());
+        assert!(removed.is_ok());
+        assert!(missing.is_err());
+    }
+}
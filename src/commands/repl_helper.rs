@@ -0,0 +1,405 @@
+use crate::api::client::{ApiClient, ApiError, ClientOptions};
+use crate::api::pagination::{PageRequest, DEFAULT_PAGE_SIZE};
+use crate::commands::session::{new_command_flags, SlashCommand, CMD_ATTACH, CMD_CONNECT, CMD_EDIT, CMD_NEW, CMD_OPEN, SLASH_COMMANDS};
+use colored::Colorize;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::{Hint, Hinter};
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// How long session IDs fetched for completion are reused
+pub const SESSION_ID_CACHE_TTL: Duration = Duration::from_secs(300);
+/// Longest the prompt waits for session IDs to complete
+pub const SESSION_ID_FETCH_TIMEOUT: Duration = Duration::from_secs(2);
+
+type FetchSessionIds = Box<dyn Fn() -> Result<Vec<String>, ApiError>>;
+
+/// Session IDs offered when completing `/connect` and `/open`
+///
+/// They are fetched the first time they are needed, and again once they are
+/// older than `SESSION_ID_CACHE_TTL`.
+pub struct SessionIdCache {
+    fetch: FetchSessionIds,
+    cached: RefCell<Option<(Instant, Vec<String>)>>,
+}
+
+impl SessionIdCache {
+    pub fn new(fetch: FetchSessionIds) -> Self {
+        Self {
+            fetch,
+            cached: RefCell::new(None),
+        }
+    }
+    
+    /// Cached IDs, fetching them when missing or stale
+    ///
+    /// A failed fetch keeps the IDs known so far rather than interrupting the
+    /// prompt, and is not retried until the cache would expire again.
+    fn ids(&self) -> Vec<String> {
+        let mut cached = self.cached.borrow_mut();
+        match &mut *cached {
+            Some((fetched, _)) if fetched.elapsed() < SESSION_ID_CACHE_TTL => {}
+            stale => {
+                let known = stale.take().map(|(_, ids)| ids).unwrap_or_default();
+                let ids = (self.fetch)().unwrap_or(known);
+                *stale = Some((Instant::now(), ids));
+            }
+        }
+        cached.as_ref().map(|(_, ids)| ids.clone()).unwrap_or_default()
+    }
+    
+    /// Replace the cache with a list that was just fetched, such as by `/sessions`
+    pub fn replace(&self, ids: Vec<String>) {
+        *self.cached.borrow_mut() = Some((Instant::now(), ids));
+    }
+    
+    /// Add a session created or connected to since the last fetch
+    pub fn insert(&self, id: &str) {
+        if let Some((_, ids)) = &mut *self.cached.borrow_mut() {
+            if !ids.iter().any(|known| known == id) {
+                ids.insert(0, id.to_string());
+            }
+        }
+    }
+}
+
+/// How a word typed after `/` relates to the known slash commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandMatch {
+    Known,
+    /// The start of one or more commands, still being typed
+    Partial,
+    Unknown,
+}
+
+fn find_command(name: &str) -> Option<&'static SlashCommand> {
+    SLASH_COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Whether the command word of `line` is a slash command; `None` for messages
+fn match_command(line: &str) -> Option<CommandMatch> {
+    if !line.starts_with('/') {
+        return None;
+    }
+    let word = line.split_whitespace().next().unwrap_or(line);
+    let typing = word.len() == line.len();
+    Some(if find_command(word).is_some() {
+        CommandMatch::Known
+    } else if typing && SLASH_COMMANDS.iter().any(|command| command.name.starts_with(word)) {
+        CommandMatch::Partial
+    } else {
+        CommandMatch::Unknown
+    })
+}
+
+/// Syntax shown after the cursor; only the rest of a command name is accepted
+/// with the right arrow key, not the argument placeholders
+pub struct CommandHint {
+    display: String,
+    completion: Option<String>,
+}
+
+impl Hint for CommandHint {
+    fn display(&self) -> &str {
+        &self.display
+    }
+    
+    fn completion(&self) -> Option<&str> {
+        self.completion.as_deref()
+    }
+}
+
+/// Completion, hints and highlighting for the interactive session
+pub struct ReplHelper {
+    pub session_ids: SessionIdCache,
+    files: FilenameCompleter,
+    new_flags: Vec<String>,
+}
+
+impl ReplHelper {
+    /// Helper completing the session IDs of the account behind `token`
+    ///
+    /// Completion only fetches the newest page of sessions, without retries and
+    /// within `SESSION_ID_FETCH_TIMEOUT`, so that Tab never blocks the prompt
+    /// for long.
+    pub fn new(token: &str, options: &ClientOptions) -> Result<Self, ApiError> {
+        let api_client = ApiClient::with_options(token, &session_id_fetch_options(options))?;
+        Ok(Self::with_session_ids(SessionIdCache::new(Box::new(move || {
+            let page = api_client.list_sessions_page(&PageRequest::first(DEFAULT_PAGE_SIZE))?;
+            Ok(page.sessions.into_iter().map(|session| session.session_id).collect())
+        }))))
+    }
+    
+    pub fn with_session_ids(session_ids: SessionIdCache) -> Self {
+        Self {
+            session_ids,
+            files: FilenameCompleter::new(),
+            new_flags: new_command_flags(),
+        }
+    }
+}
+
+/// `options` with a single attempt and timeouts of at most `SESSION_ID_FETCH_TIMEOUT`
+fn session_id_fetch_options(options: &ClientOptions) -> ClientOptions {
+    let cap = |timeout: Option<Duration>| Some(timeout.map_or(SESSION_ID_FETCH_TIMEOUT, |timeout| timeout.min(SESSION_ID_FETCH_TIMEOUT)));
+    ClientOptions {
+        timeout: cap(options.timeout),
+        connect_timeout: cap(options.connect_timeout),
+        max_attempts: Some(1),
+        ..options.clone()
+    }
+}
+
+/// Candidates among `options` starting with `prefix`, followed by a space
+fn candidates<'a>(options: impl IntoIterator<Item = &'a str>, prefix: &str) -> Vec<Pair> {
+    options.into_iter()
+        .filter(|option| option.starts_with(prefix))
+        .map(|option| Pair {
+            display: option.to_string(),
+            replacement: format!("{} ", option),
+        })
+        .collect()
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+    
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let typed = &line[..pos];
+        if !typed.starts_with('/') {
+            return Ok((0, Vec::new()));
+        }
+        let Some((command, args)) = typed.split_once(char::is_whitespace) else {
+            let names = SLASH_COMMANDS.iter().map(|command| command.name);
+            let mut pairs = candidates(names, typed);
+            // Commands without arguments are complete without a trailing space
+            for pair in &mut pairs {
+                if find_command(&pair.display).is_some_and(|command| command.args.is_empty()) {
+                    pair.replacement = pair.display.clone();
+                }
+            }
+            return Ok((0, pairs));
+        };
+        
+        let word_start = typed.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        let word = &typed[word_start..];
+        let first_argument = args.trim_start().len() == word.len();
+        match command {
+            CMD_CONNECT | CMD_OPEN if first_argument => {
+                let ids = self.session_ids.ids();
+                Ok((word_start, candidates(ids.iter().map(String::as_str), word)))
+            }
+            CMD_NEW if word.starts_with('-') => {
+                Ok((word_start, candidates(self.new_flags.iter().map(String::as_str), word)))
+            }
+            CMD_EDIT if first_argument => Ok((word_start, candidates(["last"], word))),
+            CMD_ATTACH => self.files.complete_path(line, pos),
+            _ => Ok((pos, Vec::new())),
+        }
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = CommandHint;
+    
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<CommandHint> {
+        if pos < line.len() || !line.starts_with('/') {
+            return None;
+        }
+        if let Some(command) = line.strip_suffix(' ').and_then(find_command) {
+            // Right after the command: describe the arguments
+            return (!command.args.is_empty()).then(|| CommandHint {
+                display: command.args.to_string(),
+                completion: None,
+            });
+        }
+        if line.contains(char::is_whitespace) {
+            return None;
+        }
+        
+        let mut matches = SLASH_COMMANDS.iter().filter(|command| command.name.starts_with(line));
+        let command = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+        let rest = &command.name[line.len()..];
+        let display = if command.args.is_empty() {
+            rest.to_string()
+        } else {
+            format!("{} {}", rest, command.args)
+        };
+        Some(CommandHint {
+            display,
+            completion: (!rest.is_empty()).then(|| rest.to_string()),
+        })
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        let color: fn(&str) -> String = match match_command(line) {
+            Some(CommandMatch::Known) => |word| word.yellow().to_string(),
+            Some(CommandMatch::Unknown) => |word| word.red().to_string(),
+            Some(CommandMatch::Partial) | None => return Cow::Borrowed(line),
+        };
+        let end = line.find(char::is_whitespace).unwrap_or(line.len());
+        Cow::Owned(format!("{}{}", color(&line[..end]), &line[end..]))
+    }
+    
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(hint.dimmed().to_string())
+    }
+    
+    fn highlight_char(&self, line: &str, _pos: usize, _kind: CmdKind) -> bool {
+        line.starts_with('/')
+    }
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::DefaultHistory;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    
+    fn helper(ids: &[&str]) -> (ReplHelper, Rc<Cell<usize>>) {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let fetches = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&fetches);
+        let cache = SessionIdCache::new(Box::new(move || {
+            counter.set(counter.get() + 1);
+            Ok(ids.clone())
+        }));
+        (ReplHelper::with_session_ids(cache), fetches)
+    }
+    
+    fn complete(helper: &ReplHelper, line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        let (start, pairs) = helper.complete(line, line.len(), &Context::new(&history)).unwrap();
+        (start, pairs.into_iter().map(|pair| pair.replacement).collect())
+    }
+    
+    fn hint(helper: &ReplHelper, line: &str) -> Option<(String, Option<String>)> {
+        let history = DefaultHistory::new();
+        helper.hint(line, line.len(), &Context::new(&history))
+            .map(|hint| (hint.display, hint.completion))
+    }
+    
+    #[test]
+    fn test_completes_commands() {
+        let (helper, fetches) = helper(&[]);
+        assert_eq!(complete(&helper, "/co"), (0, vec!["/connect ".to_string()]));
+        assert_eq!(complete(&helper, "/q"), (0, vec!["/quit".to_string()]));
+        assert_eq!(complete(&helper, "/").1.len(), SLASH_COMMANDS.len());
+        assert!(complete(&helper, "fix the /co").1.is_empty());
+        assert_eq!(complete(&helper, "/new --ti"), (5, vec!["--title ".to_string()]));
+        assert_eq!(complete(&helper, "/new --title x --unl"), (15, vec!["--unlisted ".to_string()]));
+        assert_eq!(complete(&helper, "/edit l"), (6, vec!["last ".to_string()]));
+        assert_eq!(fetches.get(), 0);
+    }
+    
+    #[test]
+    fn test_completes_cached_session_ids() {
+        let (helper, fetches) = helper(&["devin-abc", "devin-abd", "devin-xyz"]);
+        assert_eq!(complete(&helper, "/connect devin-ab"), (9, vec!["devin-abc ".to_string(), "devin-abd ".to_string()]));
+        assert_eq!(complete(&helper, "/open devin-x"), (6, vec!["devin-xyz ".to_string()]));
+        assert!(complete(&helper, "/connect devin-abc dev").1.is_empty());
+        assert_eq!(fetches.get(), 1);
+        
+        helper.session_ids.insert("devin-new");
+        helper.session_ids.insert("devin-abc");
+        assert_eq!(complete(&helper, "/connect devin-n").1, vec!["devin-new "]);
+        helper.session_ids.replace(vec!["devin-other".to_string()]);
+        assert_eq!(complete(&helper, "/connect devin-").1, vec!["devin-other "]);
+        assert_eq!(fetches.get(), 1);
+    }
+    
+    #[test]
+    fn test_failed_fetch_completes_nothing() {
+        let helper = ReplHelper::with_session_ids(SessionIdCache::new(Box::new(|| {
+            Err(ApiError::ConnectionError("offline".to_string()))
+        })));
+        assert!(complete(&helper, "/connect dev").1.is_empty());
+    }
+    
+    #[test]
+    fn test_fetches_one_page_of_session_ids_without_retries() {
+        let mut server = mockito::Server::new();
+        let page = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::UrlEncoded("offset".to_string(), "0".to_string()))
+            .with_status(200)
+            .with_body(r#"{"sessions":[{"session_id":"devin-abc","status":"running","created_at":"2024-01-01T00:00:00Z","updated_at":null}],"next_cursor":"more"}"#)
+            .expect(1)
+            .create();
+        let options = ClientOptions {
+            api_url: Some(server.url()),
+            ..ClientOptions::default()
+        };
+        let helper = ReplHelper::new("test-token", &options).unwrap();
+        assert_eq!(complete(&helper, "/connect dev"), (9, vec!["devin-abc ".to_string()]));
+        page.assert();
+        
+        let mut server = mockito::Server::new();
+        let unavailable = server.mock("GET", "/v1/sessions")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(1)
+            .create();
+        let options = ClientOptions {
+            api_url: Some(server.url()),
+            max_attempts: Some(5),
+            ..ClientOptions::default()
+        };
+        let helper = ReplHelper::new("test-token", &options).unwrap();
+        assert!(complete(&helper, "/connect dev").1.is_empty());
+        unavailable.assert();
+    }
+    
+    #[test]
+    fn test_session_id_fetch_timeouts_are_capped() {
+        let options = ClientOptions {
+            timeout: Some(Duration::from_secs(60)),
+            connect_timeout: Some(Duration::from_secs(1)),
+            max_attempts: Some(5),
+            ..ClientOptions::default()
+        };
+        let fetch_options = session_id_fetch_options(&options);
+        assert_eq!(fetch_options.timeout, Some(SESSION_ID_FETCH_TIMEOUT));
+        assert_eq!(fetch_options.connect_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(fetch_options.max_attempts, Some(1));
+        assert_eq!(session_id_fetch_options(&ClientOptions::default()).timeout, Some(SESSION_ID_FETCH_TIMEOUT));
+    }
+    
+    #[test]
+    fn test_hints_command_syntax() {
+        let (helper, _) = helper(&[]);
+        assert_eq!(hint(&helper, "/con"), Some(("nect <session_id>".to_string(), Some("nect".to_string()))));
+        assert_eq!(hint(&helper, "/connect"), Some((" <session_id>".to_string(), None)));
+        assert_eq!(hint(&helper, "/connect "), Some(("<session_id>".to_string(), None)));
+        assert_eq!(hint(&helper, "/qu"), Some(("it".to_string(), Some("it".to_string()))));
+        assert_eq!(hint(&helper, "/"), None);
+        assert_eq!(hint(&helper, "/connect abc"), None);
+        assert_eq!(hint(&helper, "hello /co"), None);
+    }
+    
+    #[test]
+    fn test_matches_commands_for_highlighting() {
+        assert_eq!(match_command("/connect abc"), Some(CommandMatch::Known));
+        assert_eq!(match_command("/conn"), Some(CommandMatch::Partial));
+        assert_eq!(match_command("/conn abc"), Some(CommandMatch::Unknown));
+        assert_eq!(match_command("/deploy"), Some(CommandMatch::Unknown));
+        assert_eq!(match_command("deploy /quit"), None);
+        
+        let (helper, _) = helper(&[]);
+        assert_eq!(helper.highlight("just a message", 0), "just a message");
+        assert!(helper.highlight("/deploy now", 0).ends_with(" now"));
+    }
+}
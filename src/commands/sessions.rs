@@ -0,0 +1,640 @@
+use crate::api::client::{ApiClient, ClientOptions};
+use crate::api::models::SessionDetails;
+use crate::commands::open_in_browser;
+use crate::config::get_api_token;
+use crate::output::{print_value, session_table, OutputFormat, Table};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::time::{Duration, SystemTime};
+
+/// Number of sessions `list` shows unless `--limit` or `--all` is given
+pub const DEFAULT_LIST_LIMIT: usize = 50;
+
+/// Field to sort listed sessions by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SessionSort {
+    #[default]
+    Created,
+    Updated,
+    Status,
+    Id,
+}
+
+/// Direction to sort listed sessions in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Which sessions `list` shows and in what order
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    /// Only sessions with one of these statuses
+    pub statuses: Vec<String>,
+    /// Only sessions carrying all of these tags
+    pub tags: Vec<String>,
+    /// Only sessions created at or after this date (`YYYY-MM-DD` or RFC 3339)
+    pub created_after: Option<String>,
+    /// Only sessions created before this date (`YYYY-MM-DD` or RFC 3339)
+    pub created_before: Option<String>,
+    pub sort: SessionSort,
+    pub order: SortOrder,
+    /// Show at most this many sessions
+    pub limit: Option<usize>,
+}
+
+impl SessionFilter {
+    fn validate(&self) -> Result<()> {
+        for date in [&self.created_after, &self.created_before].into_iter().flatten() {
+            if parse_timestamp(date).is_none() {
+                return Err(anyhow!("Invalid date '{}', expected YYYY-MM-DD or an RFC 3339 timestamp", date));
+            }
+        }
+        Ok(())
+    }
+    
+    fn matches(&self, session: &SessionDetails) -> bool {
+        if !self.statuses.is_empty()
+            && !self.statuses.iter().any(|status| status.eq_ignore_ascii_case(&session.status))
+        {
+            return false;
+        }
+        if !self.tags.iter().all(|tag| session.tags.contains(tag)) {
+            return false;
+        }
+        if self.created_after.is_none() && self.created_before.is_none() {
+            return true;
+        }
+        
+        // A session whose creation time can't be read matches no date range
+        let Some(created) = parse_timestamp(&session.created_at) else {
+            return false;
+        };
+        if let Some(after) = self.created_after.as_deref().and_then(parse_timestamp) {
+            if created < after {
+                return false;
+            }
+        }
+        if let Some(before) = self.created_before.as_deref().and_then(parse_timestamp) {
+            if created >= before {
+                return false;
+            }
+        }
+        true
+    }
+    
+    fn compare(&self, a: &SessionDetails, b: &SessionDetails) -> Ordering {
+        let ordering = match self.sort {
+            SessionSort::Created => compare_timestamps(&a.created_at, &b.created_at),
+            SessionSort::Updated => match (&a.updated_at, &b.updated_at) {
+                (Some(a), Some(b)) => compare_timestamps(a, b),
+                (a, b) => a.cmp(b),
+            },
+            SessionSort::Status => a.status.cmp(&b.status),
+            SessionSort::Id => a.session_id.cmp(&b.session_id),
+        };
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+    
+    /// Filter, sort and truncate a list of sessions
+    pub fn apply(&self, sessions: Vec<SessionDetails>) -> Vec<SessionDetails> {
+        let mut sessions: Vec<SessionDetails> = sessions
+            .into_iter()
+            .filter(|session| self.matches(session))
+            .collect();
+        sessions.sort_by(|a, b| self.compare(a, b));
+        if let Some(limit) = self.limit {
+            sessions.truncate(limit);
+        }
+        sessions
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date, taken as midnight UTC, or an RFC 3339 timestamp
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    if value.len() == "2024-01-01".len() {
+        return humantime::parse_rfc3339_weak(&format!("{} 00:00:00", value)).ok();
+    }
+    
+    // humantime only reads UTC times, so the offset is applied here
+    let (local, offset) = match value.strip_suffix(['Z', 'z']) {
+        Some(local) => (local, 0),
+        None => {
+            let split = value.len().checked_sub(6)?;
+            let (local, offset) = (value.get(..split)?, value.get(split..)?);
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let (hours, minutes): (i64, i64) = (hours.parse().ok()?, minutes.parse().ok()?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            (local, sign * (hours * 3600 + minutes * 60))
+        }
+    };
+    let time = humantime::parse_rfc3339_weak(local).ok()?;
+    let shift = Duration::from_secs(offset.unsigned_abs());
+    if offset > 0 {
+        time.checked_sub(shift)
+    } else {
+        time.checked_add(shift)
+    }
+}
+
+/// Order two timestamps by the time they stand for, whatever their offsets
+fn compare_timestamps(a: &str, b: &str) -> Ordering {
+    parse_timestamp(a).cmp(&parse_timestamp(b)).then_with(|| a.cmp(b))
+}
+
+#[derive(Serialize)]
+struct SessionUrl<'a> {
+    session_id: &'a str,
+    url: &'a str,
+    opened: bool,
+}
+
+#[derive(Serialize)]
+struct TerminatedSession<'a> {
+    session_id: &'a str,
+    terminated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SessionTags<'a> {
+    session_id: &'a str,
+    tags: &'a [String],
+}
+
+/// Create an API client from the configured token
+pub fn api_client(profile: Option<&str>, options: &ClientOptions, format: OutputFormat) -> Result<ApiClient> {
+    let token = match get_api_token(profile) {
+        Ok(token) => token,
+        Err(e) => {
+            if format.is_human() {
+                println!("{} {}", "✗ API token not configured:".red(), e);
+                println!("\nRun 'devin configure' to set up your API token.");
+            }
+            return Err(e);
+        }
+    };
+    
+    Ok(ApiClient::with_options(&token, options)?)
+}
+
+/// Fetch the sessions matching the filter, sorted and limited
+///
+/// The API lists sessions newest first, so in that order pages are fetched
+/// lazily until `limit` matching sessions have been found. Any other order
+/// needs every session before the first can be known. Also returns whether
+/// more sessions matched than are returned.
+fn fetch(
+    api_client: &ApiClient,
+    filter: &SessionFilter,
+    all: bool,
+    page_size: usize,
+) -> Result<(Vec<SessionDetails>, bool)> {
+    filter.validate()?;
+    let limit = if all { None } else { Some(filter.limit.unwrap_or(DEFAULT_LIST_LIMIT)) };
+    let api_order = filter.sort == SessionSort::Created && filter.order == SortOrder::Desc;
+    
+    let mut matching = Vec::new();
+    let mut more_available = false;
+    for session in api_client.sessions(page_size) {
+        let session = session?;
+        if api_order && limit.is_some_and(|limit| matching.len() >= limit) {
+            more_available = true;
+            break;
+        }
+        if filter.matches(&session) {
+            matching.push(session);
+        }
+    }
+    
+    if let Some(limit) = limit {
+        more_available |= matching.len() > limit;
+    }
+    let mut sessions = filter.apply(matching);
+    sessions.truncate(limit.unwrap_or(usize::MAX));
+    Ok((sessions, more_available))
+}
+
+/// List sessions matching the filter
+pub fn list(
+    api_client: &ApiClient,
+    filter: &SessionFilter,
+    all: bool,
+    page_size: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    let (sessions, more_available) = fetch(api_client, filter, all, page_size)?;
+    
+    if print_value(format, &sessions)? {
+        return Ok(());
+    }
+    if sessions.is_empty() {
+        println!("No sessions found.");
+    } else {
+        println!("{}", session_table(&sessions).render());
+    }
+    if more_available && filter.limit.is_none() {
+        println!("\nShowing the first {} sessions. Use --limit or --all to see more.", DEFAULT_LIST_LIMIT);
+    }
+    Ok(())
+}
+
+/// Show the details of a single session
+pub fn show(api_client: &ApiClient, session_id: &str, format: OutputFormat) -> Result<()> {
+    let details = api_client.get_session_details(session_id)?;
+    
+    if print_value(format, &details)? {
+        return Ok(());
+    }
+    
+    let mut table = Table::new(&["FIELD", "VALUE"]);
+    table.add_row(vec!["Session ID".to_string(), details.session_id.clone()]);
+    table.add_row(vec!["Status".to_string(), details.status.clone()]);
+    table.add_row(vec!["Created".to_string(), details.created_at.clone()]);
+    table.add_row(vec![
+        "Updated".to_string(),
+        details.updated_at.clone().unwrap_or_else(|| "-".to_string()),
+    ]);
+    table.add_row(vec!["Tags".to_string(), details.tags.join(", ")]);
+    table.add_row(vec!["URL".to_string(), details.url.clone().unwrap_or_default()]);
+    table.add_row(vec!["Messages".to_string(), details.messages.len().to_string()]);
+    println!("{}", table.render());
+    
+    if let Some(reply) = details.last_devin_message() {
+        println!("\n{}", "Last message from Devin:".bold());
+        println!("{}", reply.message);
+    }
+    Ok(())
+}
+
+/// Open a session in the web app, or only print its address
+pub fn open(api_client: &ApiClient, session_id: &str, print_url_only: bool, format: OutputFormat) -> Result<()> {
+    let details = api_client.get_session_details(session_id)?;
+    let url = details.url.unwrap_or_else(|| api_client.session_url(session_id));
+    
+    if !print_url_only {
+        open_in_browser(&url)?;
+    }
+    let output = SessionUrl {
+        session_id,
+        url: &url,
+        opened: !print_url_only,
+    };
+    if print_value(format, &output)? {
+        return Ok(());
+    }
+    if print_url_only {
+        println!("{}", url);
+    } else {
+        println!("{} {}", "✓ Opened".green(), url);
+    }
+    Ok(())
+}
+
+/// Terminate one or more sessions
+///
+/// Every session is tried even when one fails, and each result is reported.
+/// The command then fails with the first error, naming every failed session.
+pub fn terminate(api_client: &ApiClient, session_ids: &[String], format: OutputFormat) -> Result<()> {
+    let mut results = Vec::new();
+    let mut first_error = None;
+    for session_id in session_ids {
+        let result = api_client.terminate_session(session_id);
+        if format.is_human() {
+            match &result {
+                Ok(()) => println!("{} {}", "✓ Terminated session".green(), session_id),
+                Err(e) => println!("{} {}: {}", "✗ Failed to terminate session".red(), session_id, e),
+            }
+        }
+        results.push(TerminatedSession {
+            session_id,
+            terminated: result.is_ok(),
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    }
+    
+    print_value(format, &results)?;
+    match first_error {
+        Some(e) => {
+            let failed: Vec<&str> = results.iter()
+                .filter(|result| !result.terminated)
+                .map(|result| result.session_id)
+                .collect();
+            Err(anyhow::Error::new(e).context(format!(
+                "Failed to terminate {} of {} sessions: {}",
+                failed.len(),
+                results.len(),
+                failed.join(", ")
+            )))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Add and remove tags on a session
+pub fn tag(
+    api_client: &ApiClient,
+    session_id: &str,
+    add: &[String],
+    remove: &[String],
+    format: OutputFormat,
+) -> Result<()> {
+    if add.is_empty() && remove.is_empty() {
+        return Err(anyhow!("Give at least one tag to add, or --remove a tag"));
+    }
+    
+    let details = api_client.get_session_details(session_id)?;
+    let mut tags: Vec<String> = details.tags
+        .into_iter()
+        .filter(|tag| !remove.contains(tag))
+        .collect();
+    for tag in add {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    
+    api_client.update_session_tags(session_id, &tags)?;
+    
+    if !print_value(format, &SessionTags { session_id, tags: &tags })? {
+        let shown = if tags.is_empty() { "(none)".to_string() } else { tags.join(", ") };
+        println!("{} {}: {}", "✓ Updated tags for session".green(), session_id, shown);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::client::ApiError;
+    
+    fn session(id: &str, status: &str, created_at: &str, tags: &[&str]) -> SessionDetails {
+        SessionDetails {
+            session_id: id.to_string(),
+            status: status.to_string(),
+            created_at: created_at.to_string(),
+            updated_at: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            messages: Vec::new(),
+            url: None,
+        }
+    }
+    
+    fn sessions() -> Vec<SessionDetails> {
+        vec![
+            session("a", "running", "2024-01-01T10:00:00Z", &["ci"]),
+            session("b", "finished", "2024-02-01T10:00:00Z", &["ci", "backend"]),
+            session("c", "blocked", "2024-03-01T10:00:00Z", &[]),
+        ]
+    }
+    
+    fn ids(sessions: &[SessionDetails]) -> Vec<&str> {
+        sessions.iter().map(|session| session.session_id.as_str()).collect()
+    }
+    
+    #[test]
+    fn test_filter_defaults_to_newest_first() {
+        let result = SessionFilter::default().apply(sessions());
+        assert_eq!(ids(&result), vec!["c", "b", "a"]);
+    }
+    
+    #[test]
+    fn test_filter_by_status_tag_and_date() {
+        let filter = SessionFilter {
+            statuses: vec!["RUNNING".to_string(), "finished".to_string()],
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["b", "a"]);
+        
+        let filter = SessionFilter {
+            tags: vec!["ci".to_string(), "backend".to_string()],
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["b"]);
+        
+        let filter = SessionFilter {
+            created_after: Some("2024-01-15".to_string()),
+            created_before: Some("2024-03-01".to_string()),
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["b"]);
+    }
+    
+    #[test]
+    fn test_filter_sort_and_limit() {
+        let filter = SessionFilter {
+            sort: SessionSort::Status,
+            order: SortOrder::Asc,
+            limit: Some(2),
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["c", "b"]);
+    }
+    
+    #[test]
+    fn test_filter_rejects_invalid_dates() {
+        let filter = SessionFilter {
+            created_after: Some("last week".to_string()),
+            ..SessionFilter::default()
+        };
+        assert!(filter.validate().is_err());
+        
+        let filter = SessionFilter {
+            created_before: Some("2024-01-01T10:00:00+25:00".to_string()),
+            ..SessionFilter::default()
+        };
+        assert!(filter.validate().is_err());
+    }
+    
+    #[test]
+    fn test_filter_compares_times_across_offsets() {
+        assert_eq!(parse_timestamp("2024-01-01T12:00:00+02:00"), parse_timestamp("2024-01-01T10:00:00Z"));
+        assert_eq!(parse_timestamp("2024-01-01T05:30:00.5-04:30"), parse_timestamp("2024-01-01T10:00:00.5Z"));
+        assert_eq!(parse_timestamp("2024-01-01"), parse_timestamp("2024-01-01T00:00:00Z"));
+        
+        // Created at 09:00 UTC, which sorts after "2024-01-01T10" as a string
+        let sessions = || vec![
+            session("a", "running", "2024-01-01T11:00:00+02:00", &[]),
+            session("b", "running", "2024-01-01T10:30:00Z", &[]),
+        ];
+        let filter = SessionFilter {
+            created_after: Some("2024-01-01T10:00:00Z".to_string()),
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["b"]);
+        
+        let filter = SessionFilter {
+            created_before: Some("2024-01-01T12:00:00+02:00".to_string()),
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["a"]);
+        
+        let filter = SessionFilter {
+            order: SortOrder::Asc,
+            ..SessionFilter::default()
+        };
+        assert_eq!(ids(&filter.apply(sessions())), vec!["a", "b"]);
+    }
+    
+    #[test]
+    fn test_list_stops_fetching_at_limit() {
+        let mut server = mockito::Server::new();
+        let mut page = |offset: &str, ids: &[&str]| {
+            let sessions: Vec<_> = ids.iter()
+                .map(|id| serde_json::json!({"session_id": id, "status": "running", "created_at": "2024-01-01T00:00:00Z", "updated_at": null}))
+                .collect();
+            server.mock("GET", "/v1/sessions")
+                .match_query(mockito::Matcher::AllOf(vec![
+                    mockito::Matcher::UrlEncoded("limit".into(), "2".into()),
+                    mockito::Matcher::UrlEncoded("offset".into(), offset.into()),
+                ]))
+                .with_status(200)
+                .with_body(serde_json::json!({"sessions": sessions}).to_string())
+        };
+        let first = page("0", &["a", "b"]).create();
+        let second = page("2", &["c", "d"]).create();
+        let third = page("4", &["e"]).expect(0).create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let filter = SessionFilter {
+            limit: Some(3),
+            ..SessionFilter::default()
+        };
+        let result = list(&api_client, &filter, false, 2, OutputFormat::Json);
+        
+        assert!(result.is_ok());
+        first.assert();
+        second.assert();
+        third.assert();
+    }
+    
+    #[test]
+    fn test_list_sorts_before_limiting() {
+        let mut server = mockito::Server::new();
+        let mut page = |offset: &str, ids: &[&str]| {
+            let sessions: Vec<_> = ids.iter()
+                .map(|id| serde_json::json!({"session_id": id, "status": "running", "created_at": "2024-01-01T00:00:00Z", "updated_at": null}))
+                .collect();
+            server.mock("GET", "/v1/sessions")
+                .match_query(mockito::Matcher::AllOf(vec![
+                    mockito::Matcher::UrlEncoded("limit".into(), "2".into()),
+                    mockito::Matcher::UrlEncoded("offset".into(), offset.into()),
+                ]))
+                .with_status(200)
+                .with_body(serde_json::json!({"sessions": sessions}).to_string())
+                .create()
+        };
+        let _first = page("0", &["d", "c"]);
+        let _second = page("2", &["b", "a"]);
+        let _third = page("4", &[]);
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let filter = SessionFilter {
+            sort: SessionSort::Id,
+            order: SortOrder::Asc,
+            limit: Some(2),
+            ..SessionFilter::default()
+        };
+        let (sessions, more_available) = fetch(&api_client, &filter, false, 2).unwrap();
+        
+        assert_eq!(ids(&sessions), vec!["a", "b"]);
+        assert!(more_available);
+    }
+    
+    #[test]
+    fn test_tag_merges_with_existing_tags() {
+        let mut server = mockito::Server::new();
+        let _details = server.mock("GET", "/v1/sessions/b")
+            .with_status(200)
+            .with_body(r#"{"session_id":"b","status":"finished","created_at":"2024-02-01T10:00:00Z","updated_at":null,"tags":["ci","backend"]}"#)
+            .create();
+        let update = server.mock("PUT", "/v1/sessions/b/tags")
+            .match_body(mockito::Matcher::Json(serde_json::json!({"tags": ["ci", "urgent"]})))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let result = tag(
+            &api_client,
+            "b",
+            &["urgent".to_string()],
+            &["backend".to_string()],
+            OutputFormat::Json,
+        );
+        
+        update.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_terminate_tries_every_session() {
+        let mut server = mockito::Server::new();
+        let terminated: Vec<_> = ["a", "c"].iter()
+            .map(|id| server.mock("DELETE", format!("/v1/sessions/{}", id).as_str())
+                .with_status(200)
+                .with_body("{}")
+                .create())
+            .collect();
+        let _missing = server.mock("DELETE", "/v1/sessions/b")
+            .with_status(404)
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let ids = ["a", "b", "c"].map(String::from);
+        let error = terminate(&api_client, &ids, OutputFormat::Json).unwrap_err();
+        
+        for mock in &terminated {
+            mock.assert();
+        }
+        assert!(error.to_string().contains("Failed to terminate 1 of 3 sessions: b"));
+        assert!(matches!(error.downcast_ref::<ApiError>(), Some(ApiError::NotFound { .. })));
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_open_uses_browser_variable() {
+        let _guard = crate::config::ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let mut server = mockito::Server::new();
+        let _details = server.mock("GET", "/v1/sessions/devin-b")
+            .with_status(200)
+            .with_body(r#"{"session_id":"devin-b","status":"running","created_at":"2024-02-01T10:00:00Z","updated_at":null}"#)
+            .create();
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        
+        let opened_file = tempfile::NamedTempFile::new().unwrap();
+        // The first browser fails, so the second one is used
+        std::env::set_var("BROWSER", format!("false:printf %s >{}", opened_file.path().display()));
+        let opened = open(&api_client, "devin-b", false, OutputFormat::Json);
+        std::env::set_var("BROWSER", "false");
+        let failed = open(&api_client, "devin-b", false, OutputFormat::Json);
+        let printed = open(&api_client, "devin-b", true, OutputFormat::Json);
+        std::env::remove_var("BROWSER");
+        
+        assert!(opened.is_ok());
+        let url = std::fs::read_to_string(opened_file.path()).unwrap();
+        assert_eq!(url, format!("{}/sessions/b", server.url()));
+        assert!(failed.unwrap_err().to_string().contains("Could not open a browser"));
+        assert!(printed.is_ok());
+    }
+}
@@ -1,18 +1,42 @@
-use crate::config::get_api_token;
+use crate::config::{get_api_token, get_profile_name, get_token_storage, TokenStorage};
+use crate::output::{print_value, OutputFormat};
 use anyhow::Result;
 use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute() -> Result<()> {
+#[derive(Serialize)]
+struct ShowOutput {
+    profile: String,
+    api_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_storage: Option<TokenStorage>,
+}
+
+pub fn execute(profile: Option<&str>, format: OutputFormat) -> Result<()> {
     // Get the API token
-    match get_api_token() {
+    match get_api_token(profile) {
         Ok(token) => {
-            println!("API Token: {}", mask_token(&token));
+            let output = ShowOutput {
+                profile: get_profile_name(profile)?,
+                api_token: mask_token(&token),
+                // None when the token comes from the environment
+                token_storage: get_token_storage(profile)?,
+            };
+            if !print_value(format, &output)? {
+                println!("Profile: {}", output.profile);
+                println!("API Token: {}", output.api_token);
+                if let Some(storage) = output.token_storage {
+                    println!("Token storage: {}", storage.describe());
+                }
+            }
             Ok(())
         }
         Err(e) => {
-            println!("{} {}", "✗ API token not configured:".red(), e);
-            println!("\nRun 'devin configure <token>' to set up your API token.");
-            Err(e.into())
+            if format.is_human() {
+                println!("{} {}", "✗ API token not configured:".red(), e);
+                println!("\nRun 'devin configure' to set up your API token.");
+            }
+            Err(e)
         }
     }
 }
@@ -32,8 +56,8 @@ pub fn mask_token(token: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ENV_LOCK;
     use std::env;
-    // Removed unused import
     
     #[test]
     fn test_mask_token() {
@@ -44,14 +68,19 @@ mod tests {
     
     #[test]
     fn test_execute_with_token() {
-        // Set the environment variable for testing
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        // Set the environment variables for testing, away from the real config
+        let temp_dir = tempfile::tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
         env::set_var("DEVIN_API_TOKEN", "test-token-123");
         
         // Execute the command
-        let result = execute();
+        let result = execute(None, OutputFormat::Table);
         
         // Clean up
         env::remove_var("DEVIN_API_TOKEN");
+        env::remove_var("CONFY_CONFIG_PATH");
         
         // Check the result
         assert!(result.is_ok());
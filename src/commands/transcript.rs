@@ -0,0 +1,199 @@
+use crate::config::state_dir;
+use crate::output::MarkdownRenderer;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Most entries kept in the REPL input history
+pub const MAX_HISTORY: usize = 1000;
+
+/// Transcript entries shown when reconnecting to a session
+pub const REPLAYED_ENTRIES: usize = 20;
+
+/// File of the REPL input history, shared by every session
+pub fn history_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("history.txt"))
+}
+
+/// Create the history file if needed, readable only by the current user
+///
+/// rustyline leaves the permissions of the files it writes to the umask, so
+/// the file is made private before rustyline first touches it.
+pub fn make_private(path: &Path) -> Result<()> {
+    append_private(path, "")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to change the permissions of {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Who said something in a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Speaker {
+    You,
+    Devin,
+}
+
+/// One message of a transcript
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// RFC 3339 time the message was sent or received
+    pub time: String,
+    pub speaker: Speaker,
+    pub text: String,
+}
+
+/// The messages exchanged in one session, one JSON object per line
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    path: PathBuf,
+}
+
+impl Transcript {
+    /// Transcript of a session in the state directory
+    pub fn for_session(session_id: &str) -> Result<Self> {
+        Ok(Self::in_dir(&state_dir()?.join("transcripts"), session_id))
+    }
+    
+    /// Transcript of a session in `dir`
+    pub fn in_dir(dir: &Path, session_id: &str) -> Self {
+        // Session IDs come from the user with /connect, so keep them to one file name
+        let name: String = session_id.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        Self { path: dir.join(format!("{}.jsonl", name)) }
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// Add a message, timestamped now
+    pub fn append(&self, speaker: Speaker, text: &str) -> Result<()> {
+        let entry = TranscriptEntry {
+            time: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            speaker,
+            text: text.to_string(),
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        append_private(&self.path, &line)
+    }
+    
+    /// Every message so far; lines that cannot be read are skipped
+    pub fn load(&self) -> Result<Vec<TranscriptEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        Ok(contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+    }
+    
+    /// Show the latest messages, so that a reconnect picks up where it left off
+    pub fn replay(&self, limit: usize) -> Result<()> {
+        let entries = self.load()?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let renderer = MarkdownRenderer::for_stdout();
+        let skipped = entries.len().saturating_sub(limit);
+        println!("{}", "Earlier in this session:".dimmed());
+        if skipped > 0 {
+            println!("{}", format!("({} older messages in {})", skipped, self.path.display()).dimmed());
+        }
+        for entry in &entries[skipped..] {
+            let speaker = match entry.speaker {
+                Speaker::You => "You:".cyan(),
+                Speaker::Devin => "Devin:".green(),
+            };
+            println!("{} {}", entry.time.dimmed(), speaker);
+            match entry.speaker {
+                Speaker::You => println!("{}", entry.text.trim_end()),
+                Speaker::Devin => println!("{}", renderer.render(&entry.text).trim_end()),
+            }
+        }
+        println!();
+        Ok(())
+    }
+}
+
+/// Append to a file readable only by the current user
+fn append_private(path: &Path, contents: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_transcript_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let transcript = Transcript::in_dir(&temp_dir.path().join("transcripts"), "devin-abc");
+        assert!(transcript.load().unwrap().is_empty());
+        
+        transcript.append(Speaker::You, "Fix the build").unwrap();
+        transcript.append(Speaker::Devin, "On it.\nThe lockfile was stale.").unwrap();
+        fs::OpenOptions::new().append(true).open(transcript.path()).unwrap().write_all(b"not json\n").unwrap();
+        
+        let entries = Transcript::in_dir(&temp_dir.path().join("transcripts"), "devin-abc").load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].speaker, Speaker::You);
+        assert_eq!(entries[0].text, "Fix the build");
+        assert_eq!(entries[1].text, "On it.\nThe lockfile was stale.");
+        assert!(humantime::parse_rfc3339(&entries[1].time).is_ok());
+        
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(transcript.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_make_private() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("history.txt");
+        make_private(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        
+        fs::write(&path, "Fix the build\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        make_private(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Fix the build\n");
+    }
+    
+    #[test]
+    fn test_transcript_file_name_stays_in_dir() {
+        let dir = Path::new("/state/transcripts");
+        assert_eq!(Transcript::in_dir(dir, "devin-abc_1").path(), dir.join("devin-abc_1.jsonl"));
+        assert_eq!(Transcript::in_dir(dir, "../../etc/passwd").path(), dir.join("______etc_passwd.jsonl"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::api::client::ApiClient;
+use crate::api::models::SessionState;
+use crate::api::poll::SessionPoller;
+use crate::commands::ExitStatusError;
+use crate::output::{print_value, OutputFormat};
+use anyhow::Result;
+use colored::Colorize;
+use std::time::{Duration, Instant};
+
+/// Exit code when the session ended without finishing the task
+pub const EXIT_SESSION_FAILED: i32 = 10;
+/// Exit code when Devin is blocked waiting for user input
+pub const EXIT_SESSION_BLOCKED: i32 = 11;
+/// Exit code when the session was still running when the wait timed out
+pub const EXIT_WAIT_TIMEOUT: i32 = 12;
+
+/// How long to wait for a session and how often to check on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaitOptions {
+    /// Give up waiting after this long
+    pub timeout: Option<Duration>,
+    /// Delay between the first status checks
+    pub poll_interval: Duration,
+    /// Upper bound for the delay, which doubles while the session is quiet
+    pub max_poll_interval: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            poll_interval: Duration::from_secs(5),
+            max_poll_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Follow a session until it finishes, fails, gets blocked or the wait times out
+///
+/// New messages from Devin are printed as they arrive. With a machine-readable
+/// format the final session details are printed instead. Anything other than a
+/// finished session is returned as an `ExitStatusError`.
+pub fn wait_for_session(
+    api_client: &ApiClient,
+    session_id: &str,
+    wait_options: &WaitOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    let started = Instant::now();
+    let mut poller = SessionPoller::new(
+        api_client.clone(),
+        session_id,
+        wait_options.poll_interval,
+        wait_options.max_poll_interval,
+    );
+    
+    loop {
+        let (details, first_new) = poller.poll()?;
+        let state = details.state();
+        
+        if format.is_human() {
+            for message in details.messages.iter().skip(first_new) {
+                if message.is_from_devin() {
+                    println!("{}", message.message);
+                }
+            }
+        }
+        
+        if state != SessionState::Running {
+            print_value(format, &details)?;
+        }
+        
+        match state {
+            SessionState::Running => {}
+            SessionState::Finished => return Ok(()),
+            SessionState::Blocked => {
+                return Err(ExitStatusError::new(
+                    EXIT_SESSION_BLOCKED,
+                    format!("Session {} is blocked waiting for input", session_id),
+                ).into());
+            }
+            SessionState::Failed => {
+                return Err(ExitStatusError::new(
+                    EXIT_SESSION_FAILED,
+                    format!("Session {} ended with status '{}'", session_id, details.status),
+                ).into());
+            }
+        }
+        
+        let mut delay = poller.delay();
+        if let Some(timeout) = wait_options.timeout {
+            let elapsed = started.elapsed();
+            if elapsed >= timeout {
+                return Err(ExitStatusError::new(
+                    EXIT_WAIT_TIMEOUT,
+                    format!("Timed out waiting for session {} (status '{}')", session_id, details.status),
+                ).into());
+            }
+            delay = delay.min(timeout - elapsed);
+        }
+        
+        poller.sleep(delay);
+    }
+}
+
+/// Print a short note that the CLI is waiting for a session
+pub fn print_waiting(session_id: &str, format: OutputFormat) {
+    if format.is_human() {
+        eprintln!("{} {}...", "Waiting for session".dimmed(), session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn wait_options() -> WaitOptions {
+        WaitOptions {
+            timeout: Some(Duration::from_secs(5)),
+            poll_interval: Duration::from_millis(1),
+            max_poll_interval: Duration::from_millis(4),
+        }
+    }
+    
+    fn session_json(status: &str, replies: &[&str]) -> String {
+        let mut messages = vec![serde_json::json!({"type": "initial_user_message", "message": "Fix the build"})];
+        messages.extend(replies.iter().map(|reply| serde_json::json!({"type": "devin_message", "message": reply})));
+        serde_json::json!({
+            "session_id": "abc",
+            "status": status,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": null,
+            "messages": messages,
+        }).to_string()
+    }
+    
+    fn exit_code(result: Result<()>) -> i32 {
+        result.unwrap_err().downcast_ref::<ExitStatusError>().unwrap().code
+    }
+    
+    #[test]
+    fn test_waits_until_finished() {
+        let mut server = mockito::Server::new();
+        let running = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("running", &["Looking at the build"]))
+            .expect(2)
+            .create();
+        let finished = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("finished", &["Looking at the build", "All done"]))
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let result = wait_for_session(&api_client, "abc", &wait_options(), OutputFormat::Table);
+        
+        running.assert();
+        finished.assert();
+        assert!(result.is_ok());
+    }
+    
+    #[test]
+    fn test_reports_failed_and_blocked_sessions() {
+        let mut server = mockito::Server::new();
+        let _failed = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("expired", &[]))
+            .create();
+        let _blocked = server.mock("GET", "/v1/sessions/def")
+            .with_status(200)
+            .with_body(session_json("blocked", &["Which branch?"]))
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let failed = wait_for_session(&api_client, "abc", &wait_options(), OutputFormat::Json);
+        assert_eq!(exit_code(failed), EXIT_SESSION_FAILED);
+        let blocked = wait_for_session(&api_client, "def", &wait_options(), OutputFormat::Json);
+        assert_eq!(exit_code(blocked), EXIT_SESSION_BLOCKED);
+    }
+    
+    #[test]
+    fn test_times_out() {
+        let mut server = mockito::Server::new();
+        let _running = server.mock("GET", "/v1/sessions/abc")
+            .with_status(200)
+            .with_body(session_json("running", &[]))
+            .create();
+        
+        let api_client = ApiClient::new_with_url("test-token", &server.url());
+        let wait_options = WaitOptions {
+            timeout: Some(Duration::from_millis(20)),
+            ..wait_options()
+        };
+        let result = wait_for_session(&api_client, "abc", &wait_options, OutputFormat::Json);
+        assert_eq!(exit_code(result), EXIT_WAIT_TIMEOUT);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::config::DEFAULT_PROFILE;
+use anyhow::{anyhow, Result};
+use toml::value::Table;
+use toml::Value;
+
+/// Version of the config file written by this build
+pub const CURRENT_CONFIG_VERSION: u32 = 3;
+
+/// One upgrade step, from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    /// Changes the table in place and describes what it changed
+    apply: fn(&mut Table) -> Vec<String>,
+}
+
+/// Every upgrade step, in order
+///
+/// 1. A single `api_token` and connection settings at the top level
+/// 2. Named profiles under `[profiles.<name>]`, without a `version` key
+/// 3. An explicit `version`, and profiles recording where their token is kept
+const MIGRATIONS: &[Migration] = &[
+    Migration { from: 1, apply: move_into_default_profile },
+    Migration { from: 2, apply: record_plaintext_tokens },
+];
+
+/// What happened while upgrading a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<String>,
+}
+
+/// Version of a config file, guessing it for files written before it was recorded
+pub fn config_version(document: &Table) -> Result<u32> {
+    match document.get("version") {
+        Some(Value::Integer(version)) if *version > 0 => Ok(*version as u32),
+        Some(value) => Err(anyhow!("Invalid config version {}", value)),
+        None if document.is_empty() => Ok(CURRENT_CONFIG_VERSION),
+        None if document.contains_key("profiles") || document.contains_key("active_profile") => Ok(2),
+        None => Ok(1),
+    }
+}
+
+/// Upgrade a parsed config file to the current version
+///
+/// Returns `None` when the file is already current. Files written by a newer
+/// version of the CLI are refused rather than risk losing their settings.
+pub fn migrate(document: &mut Table) -> Result<Option<MigrationReport>> {
+    let from_version = config_version(document)?;
+    if from_version > CURRENT_CONFIG_VERSION {
+        return Err(anyhow!(
+            "The config file has version {}, but this version of devin only understands up to {}. Please upgrade devin.",
+            from_version, CURRENT_CONFIG_VERSION
+        ));
+    }
+    if from_version == CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+    
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|migration| migration.from >= from_version) {
+        changes.extend((migration.apply)(document));
+    }
+    document.insert("version".to_string(), Value::Integer(CURRENT_CONFIG_VERSION as i64));
+    
+    Ok(Some(MigrationReport {
+        from_version,
+        to_version: CURRENT_CONFIG_VERSION,
+        changes,
+    }))
+}
+
+/// 1 → 2: move the top-level settings into the `default` profile
+fn move_into_default_profile(document: &mut Table) -> Vec<String> {
+    let settings: Table = std::mem::take(document)
+        .into_iter()
+        .filter(|(_, value)| value.as_str() != Some(""))
+        .collect();
+    if settings.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut keys: Vec<&str> = settings.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    let change = format!("Moved {} into profile '{}'", keys.join(", "), DEFAULT_PROFILE);
+    
+    let mut profiles = Table::new();
+    profiles.insert(DEFAULT_PROFILE.to_string(), Value::Table(settings));
+    document.insert("profiles".to_string(), Value::Table(profiles));
+    vec![change]
+}
+
+/// 2 → 3: make the implicit plaintext token storage explicit
+fn record_plaintext_tokens(document: &mut Table) -> Vec<String> {
+    let Some(Value::Table(profiles)) = document.get_mut("profiles") else {
+        return Vec::new();
+    };
+    
+    let mut changes = Vec::new();
+    for (name, profile) in profiles.iter_mut() {
+        let Value::Table(profile) = profile else {
+            continue;
+        };
+        let has_token = profile.get("api_token").and_then(Value::as_str).is_some_and(|token| !token.is_empty());
+        if has_token && !profile.contains_key("token_storage") {
+            profile.insert("token_storage".to_string(), Value::String("plaintext".to_string()));
+            changes.push(format!(
+                "Profile '{}' keeps its token in plaintext; run 'devin --profile {} configure --storage keyring --force' to move it",
+                name, name
+            ));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, TokenStorage};
+    
+    fn parse(contents: &str) -> Table {
+        toml::from_str(contents).unwrap()
+    }
+    
+    /// Migrate a fixture and check that the result loads as the current `Config`
+    fn migrate_fixture(contents: &str) -> (Config, Option<MigrationReport>) {
+        let mut document = parse(contents);
+        let report = migrate(&mut document).unwrap();
+        assert_eq!(config_version(&document).unwrap(), CURRENT_CONFIG_VERSION);
+        (Value::Table(document).try_into().unwrap(), report)
+    }
+    
+    #[test]
+    fn test_migrates_v1() {
+        let (config, report) = migrate_fixture(include_str!("../../tests/fixtures/config/v1.toml"));
+        let report = report.unwrap();
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.to_version, CURRENT_CONFIG_VERSION);
+        assert_eq!(report.changes.len(), 2);
+        
+        let profile = &config.profiles[DEFAULT_PROFILE];
+        assert_eq!(profile.api_token, "legacy-token-1234");
+        assert_eq!(profile.token_storage, Some(TokenStorage::Plaintext));
+        assert_eq!(config.active_profile, None);
+    }
+    
+    #[test]
+    fn test_migrates_v1_with_connection_settings() {
+        let (config, report) = migrate_fixture(include_str!("../../tests/fixtures/config/v1-connection.toml"));
+        assert!(report.unwrap().changes[0].contains("api_token, api_url, connect_timeout, proxy, timeout"));
+        
+        let profile = &config.profiles[DEFAULT_PROFILE];
+        assert_eq!(profile.api_url.as_deref(), Some("https://devin.internal.example.com"));
+        assert_eq!(profile.timeout, Some(60));
+        assert_eq!(profile.connect_timeout, Some(5));
+        assert_eq!(profile.proxy.as_deref(), Some("http://proxy.example.com:3128"));
+    }
+    
+    #[test]
+    fn test_migrates_v2() {
+        let (config, report) = migrate_fixture(include_str!("../../tests/fixtures/config/v2.toml"));
+        let report = report.unwrap();
+        assert_eq!(report.from_version, 2);
+        assert_eq!(report.changes.len(), 1);
+        assert!(report.changes[0].contains("'default'"));
+        
+        assert_eq!(config.active_profile.as_deref(), Some("team"));
+        assert_eq!(config.profiles["default"].token_storage, Some(TokenStorage::Plaintext));
+        assert_eq!(config.profiles["team"].token_storage, Some(TokenStorage::Keyring));
+        assert_eq!(config.profiles["team"].timeout, Some(60));
+    }
+    
+    #[test]
+    fn test_current_version_is_unchanged() {
+        let contents = include_str!("../../tests/fixtures/config/v3.toml");
+        let mut document = parse(contents);
+        assert_eq!(migrate(&mut document).unwrap(), None);
+        assert_eq!(document, parse(contents));
+        
+        let mut empty = Table::new();
+        assert_eq!(migrate(&mut empty).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_refuses_newer_versions() {
+        let mut document = parse("version = 99\n");
+        assert!(migrate(&mut document).unwrap_err().to_string().contains("upgrade devin"));
+        assert!(config_version(&parse("version = \"three\"\n")).is_err());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod migrate;
+mod project;
+mod redaction;
+pub mod schema;
+mod secrets;
 mod storage;
 
+pub use project::*;
+pub use redaction::*;
+pub use secrets::*;
 pub use storage::*;
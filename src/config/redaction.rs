@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Redaction mode overriding the config files
+pub const REDACTION_ENV_VAR: &str = "DEVIN_REDACTION";
+
+/// What happens to secrets found in a prompt before it is sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedactionMode {
+    /// Replace the secrets with a placeholder and send the rest
+    #[default]
+    Mask,
+    /// Ask before sending the prompt unchanged, and refuse without a terminal
+    Block,
+    /// Send prompts unchanged
+    Off,
+}
+
+impl RedactionMode {
+    /// The stricter of two modes: `off` < `mask` < `block`
+    pub fn stricter(self, other: RedactionMode) -> RedactionMode {
+        let rank = |mode| match mode {
+            RedactionMode::Off => 0,
+            RedactionMode::Mask => 1,
+            RedactionMode::Block => 2,
+        };
+        if rank(other) > rank(self) { other } else { self }
+    }
+}
+
+/// The `[redaction]` table of the user config or a project's `.devin.toml`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionSettings {
+    pub mode: Option<RedactionMode>,
+    /// Regular expressions matching secrets, on top of the built-in detectors
+    pub patterns: Vec<String>,
+}
+
+impl RedactionSettings {
+    pub fn is_empty(&self) -> bool {
+        self.mode.is_none() && self.patterns.is_empty()
+    }
+    
+    /// Check that every pattern is a valid regular expression
+    pub fn validate(&self) -> Result<()> {
+        for pattern in &self.patterns {
+            Regex::new(pattern).map_err(|e| anyhow!("Invalid redaction pattern '{}': {}", pattern, e))?;
+        }
+        Ok(())
+    }
+    
+    /// These settings with a project's `other` layered on top
+    ///
+    /// A repository can only make redaction stricter and add patterns, never
+    /// loosen what the user set up.
+    pub fn merged_with(&self, other: &RedactionSettings) -> RedactionSettings {
+        RedactionSettings {
+            mode: match other.mode {
+                Some(mode) => Some(self.mode.unwrap_or_default().stricter(mode)),
+                None => self.mode,
+            },
+            patterns: self.patterns.iter().chain(&other.patterns).cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn with_mode(mode: Option<RedactionMode>) -> RedactionSettings {
+        RedactionSettings {
+            mode,
+            patterns: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn test_project_cannot_loosen_redaction() {
+        let user = RedactionSettings {
+            mode: Some(RedactionMode::Block),
+            patterns: vec!["corp-[0-9]+".to_string()],
+        };
+        let project = RedactionSettings {
+            mode: Some(RedactionMode::Off),
+            patterns: vec!["acme_[a-z]+".to_string()],
+        };
+        let merged = user.merged_with(&project);
+        assert_eq!(merged.mode, Some(RedactionMode::Block));
+        assert_eq!(merged.patterns, vec!["corp-[0-9]+", "acme_[a-z]+"]);
+        
+        // Without a mode of their own, users get the default
+        let merged = with_mode(None).merged_with(&with_mode(Some(RedactionMode::Off)));
+        assert_eq!(merged.mode, Some(RedactionMode::Mask));
+    }
+    
+    #[test]
+    fn test_project_can_tighten_redaction() {
+        let merged = with_mode(Some(RedactionMode::Off)).merged_with(&with_mode(Some(RedactionMode::Block)));
+        assert_eq!(merged.mode, Some(RedactionMode::Block));
+        let merged = with_mode(None).merged_with(&with_mode(Some(RedactionMode::Mask)));
+        assert_eq!(merged.mode, Some(RedactionMode::Mask));
+        let merged = with_mode(Some(RedactionMode::Off)).merged_with(&with_mode(None));
+        assert_eq!(merged.mode, Some(RedactionMode::Off));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::config::secrets::TokenStorage;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use std::fmt;
+use std::path::PathBuf;
+use toml::Value;
+
+/// What a config key holds, which decides how values are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// An http(s) URL
+    Url,
+    /// An http(s) or SOCKS5 proxy URL
+    ProxyUrl,
+    /// A positive number of seconds
+    Seconds,
+    /// A positive whole number
+    Count,
+    /// A duration such as `500ms` or `2s`
+    Duration,
+    /// `true` or `false`
+    Bool,
+    /// A path to an existing file
+    File,
+    /// Free text
+    Text,
+    /// One of the token storage backends
+    TokenStorage,
+    /// A secret, never printed in full
+    Secret,
+}
+
+/// A key that can be set in a profile
+#[derive(Debug)]
+pub struct ConfigKey {
+    pub name: &'static str,
+    pub kind: ValueKind,
+    pub description: &'static str,
+    /// Global flag overriding the value, if any
+    pub flag: Option<&'static str>,
+    /// Environment variables overriding the value, in order of precedence
+    pub env: &'static [&'static str],
+    /// Only `devin configure` may change it, as it has to move the token
+    pub managed: bool,
+    /// May be set in a project's `.devin.toml`, which is usually committed
+    ///
+    /// Keys deciding where the token is sent (`api_url`, `proxy`, `ca_cert`)
+    /// are excluded, as any checkout could otherwise redirect it.
+    pub project: bool,
+}
+
+/// Every key a profile accepts
+pub const CONFIG_KEYS: &[ConfigKey] = &[
+    ConfigKey {
+        name: "api_token",
+        kind: ValueKind::Secret,
+        description: "API token, when stored in plaintext",
+        flag: None,
+        env: &["DEVIN_API_TOKEN", "DEVIN_API_KEY"],
+        managed: true,
+        project: false,
+    },
+    ConfigKey {
+        name: "token_storage",
+        kind: ValueKind::TokenStorage,
+        description: "Where the API token is kept",
+        flag: None,
+        env: &[],
+        managed: true,
+        project: false,
+    },
+    ConfigKey {
+        name: "token_command",
+        kind: ValueKind::Text,
+        description: "Command printing the API token",
+        flag: None,
+        env: &[],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "api_url",
+        kind: ValueKind::Url,
+        description: "Base URL of the Devin API",
+        flag: Some("--api-url"),
+        env: &["DEVIN_API_URL"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "timeout",
+        kind: ValueKind::Seconds,
+        description: "Total request timeout in seconds",
+        flag: Some("--timeout"),
+        env: &["DEVIN_TIMEOUT"],
+        managed: false,
+        project: true,
+    },
+    ConfigKey {
+        name: "connect_timeout",
+        kind: ValueKind::Seconds,
+        description: "Connection timeout in seconds",
+        flag: Some("--connect-timeout"),
+        env: &["DEVIN_CONNECT_TIMEOUT"],
+        managed: false,
+        project: true,
+    },
+    ConfigKey {
+        name: "proxy",
+        kind: ValueKind::ProxyUrl,
+        description: "Proxy for all API requests",
+        flag: Some("--proxy"),
+        env: &["DEVIN_PROXY"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "ca_cert",
+        kind: ValueKind::File,
+        description: "PEM bundle with extra trusted CAs",
+        flag: Some("--ca-cert"),
+        env: &["DEVIN_CA_CERT"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "max_attempts",
+        kind: ValueKind::Count,
+        description: "Attempts per API request, including the first",
+        flag: Some("--max-attempts"),
+        env: &["DEVIN_MAX_ATTEMPTS"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "retry_base_delay",
+        kind: ValueKind::Duration,
+        description: "Delay before the first retry, doubled for each further one",
+        flag: None,
+        env: &["DEVIN_RETRY_BASE_DELAY"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "retry_max_delay",
+        kind: ValueKind::Duration,
+        description: "Longest delay between two attempts",
+        flag: None,
+        env: &["DEVIN_RETRY_MAX_DELAY"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "retry_jitter",
+        kind: ValueKind::Bool,
+        description: "Randomize retry delays",
+        flag: None,
+        env: &["DEVIN_RETRY_JITTER"],
+        managed: false,
+        project: false,
+    },
+    ConfigKey {
+        name: "retry_non_idempotent",
+        kind: ValueKind::Bool,
+        description: "Also retry requests that may create a second session",
+        flag: None,
+        env: &["DEVIN_RETRY_NON_IDEMPOTENT"],
+        managed: false,
+        project: false,
+    },
+];
+
+/// Look up a key by name
+pub fn find_key(name: &str) -> Result<&'static ConfigKey> {
+    CONFIG_KEYS.iter()
+        .find(|key| key.name == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = CONFIG_KEYS.iter().map(|key| key.name).collect();
+            anyhow!("Unknown config key '{}'. Valid keys: {}", name, names.join(", "))
+        })
+}
+
+impl ConfigKey {
+    /// Check a value given on the command line and convert it for the config file
+    pub fn parse(&self, value: &str) -> Result<Value> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(anyhow!("{} must not be empty", self.name));
+        }
+        match self.kind {
+            ValueKind::Url => self.parse_url(value, &["http", "https"]),
+            ValueKind::ProxyUrl => self.parse_url(value, &["http", "https", "socks5", "socks5h"]),
+            ValueKind::Seconds => match value.parse::<i64>() {
+                Ok(seconds) if seconds > 0 => Ok(Value::Integer(seconds)),
+                _ => Err(anyhow!("{} must be a positive number of seconds, got '{}'", self.name, value)),
+            },
+            ValueKind::Count => match value.parse::<u32>() {
+                Ok(count) if count > 0 => Ok(Value::Integer(count.into())),
+                _ => Err(anyhow!("{} must be a positive whole number, got '{}'", self.name, value)),
+            },
+            ValueKind::Duration => match humantime::parse_duration(value) {
+                Ok(_) => Ok(Value::String(value.to_string())),
+                Err(e) => Err(anyhow!("{} must be a duration such as 500ms or 2s, got '{}': {}", self.name, value, e)),
+            },
+            ValueKind::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" => Ok(Value::Boolean(true)),
+                "false" => Ok(Value::Boolean(false)),
+                _ => Err(anyhow!("{} must be true or false, got '{}'", self.name, value)),
+            },
+            ValueKind::File => {
+                if !PathBuf::from(value).is_file() {
+                    return Err(anyhow!("{} must be an existing file, '{}' was not found", self.name, value));
+                }
+                Ok(Value::String(value.to_string()))
+            }
+            ValueKind::TokenStorage => {
+                let storage = TokenStorage::from_str(value, true).map_err(|_| {
+                    let names: Vec<String> = TokenStorage::value_variants().iter()
+                        .filter_map(|storage| storage.to_possible_value())
+                        .map(|value| value.get_name().to_string())
+                        .collect();
+                    anyhow!("{} must be one of {}, got '{}'", self.name, names.join(", "), value)
+                })?;
+                Ok(Value::try_from(storage)?)
+            }
+            ValueKind::Text | ValueKind::Secret => Ok(Value::String(value.to_string())),
+        }
+    }
+    
+    fn parse_url(&self, value: &str, schemes: &[&str]) -> Result<Value> {
+        let url = reqwest::Url::parse(value)
+            .map_err(|e| anyhow!("{} must be a URL, got '{}': {}", self.name, value, e))?;
+        if !schemes.contains(&url.scheme()) {
+            return Err(anyhow!(
+                "{} must be a URL with scheme {}, got '{}'",
+                self.name,
+                schemes.join(", "),
+                value
+            ));
+        }
+        Ok(Value::String(value.to_string()))
+    }
+    
+    /// Check a value read from the config file
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        match value {
+            Value::String(text) => self.parse(text).map(|_| ()),
+            Value::Integer(number) if matches!(self.kind, ValueKind::Seconds | ValueKind::Count) => {
+                self.parse(&number.to_string()).map(|_| ())
+            }
+            Value::Boolean(_) if self.kind == ValueKind::Bool => Ok(()),
+            _ => Err(anyhow!("{} has a value of the wrong type: {}", self.name, value)),
+        }
+    }
+}
+
+/// Where an effective setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueSource {
+    Flag(&'static str),
+    Env(&'static str),
+    ProjectFile(PathBuf),
+    UserFile(PathBuf),
+    Default,
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueSource::Flag(flag) => write!(f, "flag {}", flag),
+            ValueSource::Env(name) => write!(f, "env {}", name),
+            ValueSource::ProjectFile(path) => write!(f, "project file {}", path.display()),
+            ValueSource::UserFile(path) => write!(f, "user file {}", path.display()),
+            ValueSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Text shown for a config value
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_find_key() {
+        assert_eq!(find_key("api_url").unwrap().kind, ValueKind::Url);
+        let error = find_key("api-url").unwrap_err().to_string();
+        assert!(error.contains("Unknown config key"));
+        assert!(error.contains("connect_timeout"));
+    }
+    
+    #[test]
+    fn test_parse_values() {
+        let api_url = find_key("api_url").unwrap();
+        assert_eq!(api_url.parse("https://devin.example.com").unwrap().as_str(), Some("https://devin.example.com"));
+        assert!(api_url.parse("devin.example.com").is_err());
+        assert!(api_url.parse("ftp://devin.example.com").is_err());
+        let error = api_url.parse("socks5://proxy.example.com:1080").unwrap_err().to_string();
+        assert!(error.contains("scheme http, https"), "{}", error);
+        
+        let proxy = find_key("proxy").unwrap();
+        assert_eq!(
+            proxy.parse("socks5h://proxy.example.com:1080").unwrap().as_str(),
+            Some("socks5h://proxy.example.com:1080")
+        );
+        let error = proxy.parse("ftp://proxy.example.com").unwrap_err().to_string();
+        assert!(error.contains("scheme http, https, socks5, socks5h"), "{}", error);
+        
+        let timeout = find_key("timeout").unwrap();
+        assert_eq!(timeout.parse("30").unwrap().as_integer(), Some(30));
+        assert!(timeout.parse("0").is_err());
+        assert!(timeout.parse("soon").is_err());
+        
+        let storage = find_key("token_storage").unwrap();
+        assert_eq!(storage.parse("Encrypted-File").unwrap().as_str(), Some("encrypted-file"));
+        assert!(storage.parse("vault").unwrap_err().to_string().contains("keyring"));
+        
+        let max_attempts = find_key("max_attempts").unwrap();
+        assert_eq!(max_attempts.parse("5").unwrap().as_integer(), Some(5));
+        assert!(max_attempts.parse("0").is_err());
+        let base_delay = find_key("retry_base_delay").unwrap();
+        assert_eq!(base_delay.parse("250ms").unwrap().as_str(), Some("250ms"));
+        assert!(base_delay.parse("soon").is_err());
+        assert_eq!(find_key("retry_jitter").unwrap().parse("False").unwrap().as_bool(), Some(false));
+        assert!(find_key("retry_non_idempotent").unwrap().parse("yes").is_err());
+        
+        assert!(find_key("ca_cert").unwrap().parse("/does/not/exist.pem").is_err());
+        assert!(find_key("proxy").unwrap().parse("  ").is_err());
+    }
+    
+    #[test]
+    fn test_validate_file_values() {
+        let timeout = find_key("timeout").unwrap();
+        assert!(timeout.validate(&Value::Integer(10)).is_ok());
+        assert!(timeout.validate(&Value::Integer(-1)).is_err());
+        assert!(find_key("api_url").unwrap().validate(&Value::Integer(10)).is_err());
+        assert!(find_key("max_attempts").unwrap().validate(&Value::Integer(4)).is_ok());
+        assert!(find_key("retry_jitter").unwrap().validate(&Value::Boolean(false)).is_ok());
+        assert!(find_key("retry_jitter").unwrap().validate(&Value::Integer(1)).is_err());
+    }
+}
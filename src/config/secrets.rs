@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chacha20poly1305::aead::rand_core::RngCore;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Service name under which tokens are stored in the OS keyring
+const KEYRING_SERVICE: &str = "devin-cli";
+
+/// Passphrase for the encrypted token file, instead of prompting for it
+pub const PASSPHRASE_ENV_VAR: &str = "DEVIN_TOKEN_PASSPHRASE";
+
+/// Where a profile's API token is kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenStorage {
+    /// The OS keyring (Secret Service on Linux, Keychain on macOS, Credential Manager on Windows)
+    #[default]
+    Keyring,
+    /// A file next to the config file, encrypted with a passphrase
+    EncryptedFile,
+    /// The output of an external command such as `pass` or `op read`
+    Command,
+    /// The config file itself, unencrypted
+    Plaintext,
+}
+
+impl TokenStorage {
+    /// Short description for messages
+    pub fn describe(self) -> &'static str {
+        match self {
+            TokenStorage::Keyring => "the system keyring",
+            TokenStorage::EncryptedFile => "an encrypted file",
+            TokenStorage::Command => "an external command",
+            TokenStorage::Plaintext => "the config file (plaintext)",
+        }
+    }
+}
+
+/// A backend holding API tokens outside the config file
+pub trait SecretStore {
+    /// Read the token of a profile, if one has been stored
+    fn get(&self, profile: &str) -> Result<Option<String>>;
+    /// Store the token of a profile
+    fn set(&self, profile: &str, token: &str) -> Result<()>;
+    /// Forget the token of a profile
+    fn delete(&self, profile: &str) -> Result<()>;
+}
+
+/// Tokens kept in the OS keyring
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(profile: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, profile).context("Failed to open the system keyring")
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, profile: &str) -> Result<Option<String>> {
+        match Self::entry(profile)?.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read the API token from the system keyring"),
+        }
+    }
+    
+    fn set(&self, profile: &str, token: &str) -> Result<()> {
+        Self::entry(profile)?
+            .set_password(token)
+            .context("Failed to store the API token in the system keyring")
+    }
+    
+    fn delete(&self, profile: &str) -> Result<()> {
+        match Self::entry(profile)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to remove the API token from the system keyring"),
+        }
+    }
+}
+
+/// Contents of an encrypted token file
+#[derive(Serialize, Deserialize)]
+struct EncryptedToken {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Tokens encrypted with a passphrase, one file per profile
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+    
+    fn path(&self, profile: &str) -> PathBuf {
+        let name: String = profile.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.token", name))
+    }
+    
+    /// Passphrase from `DEVIN_TOKEN_PASSPHRASE`, or asked for on the terminal
+    fn passphrase() -> Result<String> {
+        if let Ok(passphrase) = env::var(PASSPHRASE_ENV_VAR) {
+            if !passphrase.is_empty() {
+                return Ok(passphrase);
+            }
+        }
+        if !std::io::stdin().is_terminal() {
+            return Err(anyhow!(
+                "The API token is encrypted. Set {} to the passphrase to decrypt it.",
+                PASSPHRASE_ENV_VAR
+            ));
+        }
+        let passphrase = rpassword::prompt_password("Passphrase for the API token: ")?;
+        if passphrase.is_empty() {
+            return Err(anyhow!("The passphrase is empty"));
+        }
+        Ok(passphrase)
+    }
+    
+    fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow!("Failed to derive the encryption key: {}", e))?;
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, profile: &str) -> Result<Option<String>> {
+        let path = self.path(profile);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let encrypted: EncryptedToken = serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a valid token file", path.display()))?;
+        
+        let salt = hex::decode(&encrypted.salt)?;
+        let nonce = hex::decode(&encrypted.nonce)?;
+        let ciphertext = hex::decode(&encrypted.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(anyhow!("{} is not a valid token file", path.display()));
+        }
+        
+        let token = Self::cipher(&Self::passphrase()?, &salt)?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow!("Failed to decrypt the API token: wrong passphrase?"))?;
+        Ok(Some(String::from_utf8(token)?))
+    }
+    
+    fn set(&self, profile: &str, token: &str) -> Result<()> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = Self::cipher(&Self::passphrase()?, &salt)?
+            .encrypt(&nonce, token.as_bytes())
+            .map_err(|_| anyhow!("Failed to encrypt the API token"))?;
+        
+        let encrypted = EncryptedToken {
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.path(profile);
+        write_private(&path, &serde_json::to_string_pretty(&encrypted)?)
+    }
+    
+    fn delete(&self, profile: &str) -> Result<()> {
+        let path = self.path(profile);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Tokens printed by an external command, for example `pass show devin` or
+/// `op read op://Private/Devin/token`
+///
+/// The command manages the secret itself, so storing or removing a token
+/// through the CLI is not possible.
+pub struct CommandStore {
+    command: String,
+}
+
+impl CommandStore {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+}
+
+impl SecretStore for CommandStore {
+    fn get(&self, _profile: &str) -> Result<Option<String>> {
+        let output = if cfg!(windows) {
+            Command::new("cmd").args(["/C", &self.command]).output()
+        } else {
+            Command::new("sh").args(["-c", &self.command]).output()
+        }
+        .with_context(|| format!("Failed to run token command '{}'", self.command))?;
+        
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Token command '{}' failed ({}): {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let token = String::from_utf8(output.stdout)
+            .context("Token command printed invalid UTF-8")?
+            .trim()
+            .to_string();
+        Ok(Some(token).filter(|token| !token.is_empty()))
+    }
+    
+    fn set(&self, _profile: &str, _token: &str) -> Result<()> {
+        Err(anyhow!(
+            "The API token is read from '{}'. Update it in your secret manager instead.",
+            self.command
+        ))
+    }
+    
+    fn delete(&self, _profile: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ENV_LOCK;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_encrypted_file_roundtrip() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        let store = EncryptedFileStore::new(temp_dir.path().join("secrets"));
+        env::set_var(PASSPHRASE_ENV_VAR, "correct horse");
+        store.set("team/prod", "secret-token").unwrap();
+        let token = store.get("team/prod");
+        let missing = store.get("personal");
+        env::set_var(PASSPHRASE_ENV_VAR, "wrong horse");
+        let wrong = store.get("team/prod");
+        env::remove_var(PASSPHRASE_ENV_VAR);
+        
+        let contents = fs::read_to_string(store.path("team/prod")).unwrap();
+        assert!(!contents.contains("secret-token"));
+        assert_eq!(token.unwrap().as_deref(), Some("secret-token"));
+        assert_eq!(missing.unwrap(), None);
+        assert!(wrong.is_err());
+        
+        store.delete("team/prod").unwrap();
+        assert!(!store.path("team/prod").exists());
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_command_store() {
+        let store = CommandStore::new("echo '  token-from-helper  '");
+        assert_eq!(store.get("default").unwrap().as_deref(), Some("token-from-helper"));
+        assert!(store.set("default", "other").is_err());
+        
+        let failing = CommandStore::new("echo 'not signed in' >&2; exit 1");
+        let error = failing.get("default").unwrap_err().to_string();
+        assert!(error.contains("not signed in"));
+    }
+}
@@ -1,26 +1,332 @@
-use anyhow::{anyhow, Result};
+use crate::api::client::ClientOptions;
+use crate::api::client::DEFAULT_API_URL;
+use crate::config::migrate::{migrate, MigrationReport, CURRENT_CONFIG_VERSION};
+use crate::config::project::load_project_config;
+use crate::config::redaction::{RedactionMode, RedactionSettings, REDACTION_ENV_VAR};
+use crate::config::schema::{display_value, find_key, ConfigKey, ValueSource, CONFIG_KEYS};
+use crate::config::secrets::{CommandStore, EncryptedFileStore, KeyringStore, SecretStore, TokenStorage};
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::value::Table;
+use toml::Value;
 
 const APP_NAME: &str = "devin";
 const CONFIG_NAME: &str = "config";
 pub const ENV_VAR_NAME: &str = "DEVIN_API_TOKEN";
+pub const API_KEY_ENV_VAR: &str = "DEVIN_API_KEY";
+
+pub const API_URL_ENV_VAR: &str = "DEVIN_API_URL";
+pub const TIMEOUT_ENV_VAR: &str = "DEVIN_TIMEOUT";
+pub const CONNECT_TIMEOUT_ENV_VAR: &str = "DEVIN_CONNECT_TIMEOUT";
+pub const PROXY_ENV_VAR: &str = "DEVIN_PROXY";
+pub const CA_CERT_ENV_VAR: &str = "DEVIN_CA_CERT";
+pub const MAX_ATTEMPTS_ENV_VAR: &str = "DEVIN_MAX_ATTEMPTS";
+pub const RETRY_BASE_DELAY_ENV_VAR: &str = "DEVIN_RETRY_BASE_DELAY";
+pub const RETRY_MAX_DELAY_ENV_VAR: &str = "DEVIN_RETRY_MAX_DELAY";
+pub const RETRY_JITTER_ENV_VAR: &str = "DEVIN_RETRY_JITTER";
+pub const RETRY_NON_IDEMPOTENT_ENV_VAR: &str = "DEVIN_RETRY_NON_IDEMPOTENT";
+
+/// Overrides the directory holding the config file (used to isolate tests)
+pub const CONFIG_PATH_ENV_VAR: &str = "CONFY_CONFIG_PATH";
+
+/// Overrides the directory holding history and session transcripts
+pub const STATE_DIR_ENV_VAR: &str = "DEVIN_STATE_DIR";
+
+/// Serializes tests that mutate process-wide environment variables
+#[cfg(test)]
+pub(crate) static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Name of the profile used when none has been selected
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Selects the profile when `--profile` is not given
+pub const PROFILE_ENV_VAR: &str = "DEVIN_PROFILE";
+
+/// Where newly configured tokens are stored unless `--storage` is given
+pub const TOKEN_STORAGE_ENV_VAR: &str = "DEVIN_TOKEN_STORAGE";
+
+/// Token and connection settings for one organization or environment
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    /// The token itself, only used with the plaintext storage
+    pub api_token: String,
+    /// Where the API token is kept; profiles without it keep it in `api_token`
+    pub token_storage: Option<TokenStorage>,
+    /// Command printing the API token, for the `command` storage
+    pub token_command: Option<String>,
+    /// Base URL of the Devin API
+    pub api_url: Option<String>,
+    /// Total request timeout in seconds
+    pub timeout: Option<u64>,
+    /// Connection timeout in seconds
+    pub connect_timeout: Option<u64>,
+    /// Proxy URL for all API requests
+    pub proxy: Option<String>,
+    /// Path to a PEM bundle with additional trusted CA certificates
+    pub ca_cert: Option<PathBuf>,
+    /// Attempts per API request, including the first
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry, such as `500ms`
+    pub retry_base_delay: Option<String>,
+    /// Longest delay between two attempts, such as `30s`
+    pub retry_max_delay: Option<String>,
+    /// Randomize retry delays
+    pub retry_jitter: Option<bool>,
+    /// Also retry requests that may create a second session
+    pub retry_non_idempotent: Option<bool>,
+}
+
+impl Profile {
+    /// Where the token of this profile is kept
+    pub fn storage(&self) -> TokenStorage {
+        self.token_storage.unwrap_or(TokenStorage::Plaintext)
+    }
+    
+    /// The value of a key as written to the config file, if it is set
+    fn value(&self, key: &str) -> Result<Option<Value>> {
+        let table = Value::try_from(self)?;
+        Ok(table.get(key).filter(|value| value.as_str() != Some("")).cloned())
+    }
+    
+    /// Timeouts of this project profile, falling back to those of `base`
+    ///
+    /// Everything else, in particular where the token is sent, comes from `base`.
+    fn layered_over(&self, base: &Profile) -> Profile {
+        Profile {
+            timeout: self.timeout.or(base.timeout),
+            connect_timeout: self.connect_timeout.or(base.connect_timeout),
+            ..base.clone()
+        }
+    }
+    
+    /// Set or remove the value of a key
+    fn set_value(&mut self, key: &str, value: Option<Value>) -> Result<()> {
+        let mut table = Value::try_from(&*self)?;
+        let entries = table.as_table_mut().ok_or_else(|| anyhow!("Invalid profile"))?;
+        match value {
+            Some(value) => entries.insert(key.to_string(), value),
+            None => entries.remove(key),
+        };
+        *self = table.try_into()?;
+        Ok(())
+    }
+}
+
+/// The effective value of a config key and where it comes from
+#[derive(Debug, Clone)]
+pub struct ResolvedValue {
+    pub key: &'static ConfigKey,
+    pub value: String,
+    pub source: ValueSource,
+}
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct Config {
-    pub api_token: String,
+    /// Shape of the file, upgraded by `config::migrate` when it is older
+    pub version: u32,
+    /// Profile used unless `--profile` or `DEVIN_PROFILE` selects another one
+    pub active_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+    /// Secret redaction, shared by every profile
+    #[serde(skip_serializing_if = "RedactionSettings::is_empty")]
+    pub redaction: RedactionSettings,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            api_token: String::new(),
+            version: CURRENT_CONFIG_VERSION,
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            redaction: RedactionSettings::default(),
         }
     }
 }
 
+/// Path of the config file the CLI reads and writes
+pub fn config_path() -> Result<PathBuf> {
+    match config_file_path() {
+        Some(path) => Ok(path),
+        None => Ok(confy::get_configuration_file_path(APP_NAME, CONFIG_NAME)?),
+    }
+}
+
+/// Path of the config file, created with defaults if it does not exist yet
+///
+/// An existing file is not read, so that a broken one can still be edited.
+pub fn ensure_config_file() -> Result<PathBuf> {
+    let path = config_path()?;
+    if !path.exists() {
+        store_config(&Config::default())?;
+    }
+    Ok(path)
+}
+
+/// Path of the config file, honoring the `CONFY_CONFIG_PATH` override
+fn config_file_path() -> Option<PathBuf> {
+    match env::var(CONFIG_PATH_ENV_VAR) {
+        Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join(format!("{}.toml", CONFIG_NAME))),
+        _ => None,
+    }
+}
+
+/// Directory holding the config file and the encrypted token files
+fn config_dir() -> Result<PathBuf> {
+    let path = config_path()?;
+    path.parent()
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("Invalid config file path {}", path.display()))
+}
+
+/// Directory for REPL history and session transcripts
+///
+/// This is `$DEVIN_STATE_DIR` when set, else `devin` in the XDG state directory
+/// (`$XDG_STATE_HOME`, defaulting to `~/.local/state`).
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = env_value(STATE_DIR_ENV_VAR) {
+        return Ok(PathBuf::from(dir));
+    }
+    // Relative paths are invalid per the XDG spec and are ignored
+    if let Some(dir) = env_value("XDG_STATE_HOME").map(PathBuf::from).filter(|dir| dir.is_absolute()) {
+        return Ok(dir.join(APP_NAME));
+    }
+    #[cfg(windows)]
+    if let Some(dir) = env_value("LOCALAPPDATA") {
+        return Ok(PathBuf::from(dir).join(APP_NAME).join("state"));
+    }
+    let home = env_value("HOME")
+        .ok_or_else(|| anyhow!("Cannot find your home directory; set {} to keep history elsewhere", STATE_DIR_ENV_VAR))?;
+    Ok(PathBuf::from(home).join(".local").join("state").join(APP_NAME))
+}
+
+/// The backend holding tokens for `storage`, or `None` for plaintext
+fn secret_store(storage: TokenStorage, profile: &Profile) -> Result<Option<Box<dyn SecretStore>>> {
+    Ok(match storage {
+        TokenStorage::Keyring => Some(Box::new(KeyringStore)),
+        TokenStorage::EncryptedFile => Some(Box::new(EncryptedFileStore::new(config_dir()?.join("secrets")))),
+        TokenStorage::Command => {
+            let command = profile.token_command.as_deref()
+                .ok_or_else(|| anyhow!("No token command configured. Run 'devin configure --token-command <COMMAND>'."))?;
+            Some(Box::new(CommandStore::new(command)))
+        }
+        TokenStorage::Plaintext => None,
+    })
+}
+
+/// Read the token of a profile from wherever it is stored
+fn profile_token(name: &str, profile: &Profile) -> Result<Option<String>> {
+    match secret_store(profile.storage(), profile)? {
+        Some(store) => store.get(name),
+        None => Ok(Some(profile.api_token.clone()).filter(|token| !token.is_empty())),
+    }
+}
+
+fn load_config() -> Result<Config> {
+    let path = config_path()?;
+    if !path.exists() {
+        let config = Config::default();
+        store_config(&config)?;
+        return Ok(config);
+    }
+    
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut document: Table = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let report = migrate(&mut document)
+        .with_context(|| format!("Failed to upgrade {}", path.display()))?;
+    let config: Config = Value::Table(document).try_into()
+        .with_context(|| format!("Failed to load {}", path.display()))?;
+    
+    if let Some(report) = report {
+        // Keep the old file in case the upgrade lost something
+        let backup = backup_path(&path, report.from_version);
+        fs::copy(&path, &backup)
+            .with_context(|| format!("Failed to back up {} before upgrading it", path.display()))?;
+        store_config(&config)?;
+        print_migration(&report, &path, &backup);
+    }
+    Ok(config)
+}
+
+/// A file name next to `path` for its backup that doesn't overwrite earlier ones
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    (0..)
+        .map(|attempt| match attempt {
+            0 => path.with_file_name(format!("{}.v{}.bak", name, version)),
+            n => path.with_file_name(format!("{}.v{}.{}.bak", name, version, n)),
+        })
+        .find(|backup| !backup.exists())
+        .unwrap_or_else(|| path.with_file_name(format!("{}.bak", name)))
+}
+
+fn print_migration(report: &MigrationReport, path: &Path, backup: &Path) {
+    eprintln!(
+        "Upgraded {} from version {} to {}:",
+        path.display(), report.from_version, report.to_version
+    );
+    for change in &report.changes {
+        eprintln!("  - {}", change);
+    }
+    eprintln!("The previous file was saved as {}", backup.display());
+}
+
+fn store_config(config: &Config) -> Result<()> {
+    match config_file_path() {
+        Some(path) => confy::store_path(path, config)?,
+        None => confy::store(APP_NAME, CONFIG_NAME, config)?,
+    }
+    Ok(())
+}
+
+/// Profile explicitly selected with `--profile` (given as `profile`) or `DEVIN_PROFILE`
+fn selected_profile(profile: Option<&str>) -> Option<String> {
+    profile.map(str::to_string)
+        .or_else(|| env_value(PROFILE_ENV_VAR))
+}
+
+/// Name of the profile in use: `--profile`, then `DEVIN_PROFILE`, then the active profile
+///
+/// Every function taking a `profile` expects the value of the `--profile`
+/// flag, `None` when it was not given.
+fn profile_name(config: &Config, profile: Option<&str>) -> String {
+    selected_profile(profile)
+        .or_else(|| config.active_profile.clone())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Settings of the profile in use
+///
+/// A profile that was explicitly selected must exist; otherwise a missing
+/// profile just means nothing has been configured yet.
+fn current_profile(config: &Config, profile: Option<&str>) -> Result<(String, Profile)> {
+    let name = profile_name(config, profile);
+    match config.profiles.get(&name) {
+        Some(profile) => Ok((name, profile.clone())),
+        None if selected_profile(profile).is_some() => Err(anyhow!(
+            "Profile '{}' not found. Run 'devin configure --profile {}' to create it.",
+            name, name
+        )),
+        None => Ok((name, Profile::default())),
+    }
+}
+
+/// Name of the profile in use
+pub fn get_profile_name(profile: Option<&str>) -> Result<String> {
+    Ok(profile_name(&load_config()?, profile))
+}
+
 /// Get the API token from environment variable or config file
-pub fn get_api_token() -> Result<String> {
+pub fn get_api_token(profile: Option<&str>) -> Result<String> {
     // First check environment variable (DEVIN_API_TOKEN)
     if let Ok(token) = env::var(ENV_VAR_NAME) {
         if !token.is_empty() {
@@ -29,47 +335,448 @@ pub fn get_api_token() -> Result<String> {
     }
     
     // Also check alternative environment variable (DEVIN_API_KEY)
-    if let Ok(token) = env::var("DEVIN_API_KEY") {
+    if let Ok(token) = env::var(API_KEY_ENV_VAR) {
         if !token.is_empty() {
             return Ok(token);
         }
     }
     
-    // Then check config file
-    let config: Config = confy::load(APP_NAME, CONFIG_NAME)?;
+    // Then check the profile in the config file
+    let (name, profile) = current_profile(&load_config()?, profile)?;
+    
+    profile_token(&name, &profile)?.ok_or_else(|| anyhow!("API token not found"))
+}
+
+/// Where the token of the profile in use is stored, if one is configured
+pub fn get_token_storage(profile: Option<&str>) -> Result<Option<TokenStorage>> {
+    let config = load_config()?;
+    let Some(profile) = config.profiles.get(&profile_name(&config, profile)) else {
+        return Ok(None);
+    };
+    Ok(match profile.storage() {
+        TokenStorage::Plaintext if profile.api_token.is_empty() => None,
+        storage => Some(storage),
+    })
+}
+
+/// Save the API token to the profile in use, creating the profile if needed
+pub fn save_api_token(profile: Option<&str>, token: &str) -> Result<()> {
+    store_api_token(profile, Some(token), None, None).map(|_| ())
+}
+
+/// Store the API token of the profile in use and return where it was stored
+///
+/// Without an explicit `storage`, the profile keeps its current storage, or
+/// uses `DEVIN_TOKEN_STORAGE`, or the system keyring with an encrypted file as
+/// the fallback. Plaintext is only used when asked for. With the command
+/// storage no token is given; `token_command` prints it instead.
+pub fn store_api_token(
+    profile: Option<&str>,
+    token: Option<&str>,
+    storage: Option<TokenStorage>,
+    token_command: Option<&str>,
+) -> Result<TokenStorage> {
+    let mut config = load_config()?;
+    let name = profile_name(&config, profile);
+    let mut profile = config.profiles.get(&name).cloned().unwrap_or_default();
+    let previous = profile.token_storage;
+    
+    if let Some(command) = token_command {
+        profile.token_command = Some(command.to_string());
+    }
+    let requested = match storage.or(previous) {
+        Some(storage) => Some(storage),
+        None => env_value(TOKEN_STORAGE_ENV_VAR)
+            .map(|value| TokenStorage::from_str(&value, true)
+                .map_err(|_| anyhow!("Invalid {} '{}'", TOKEN_STORAGE_ENV_VAR, value)))
+            .transpose()?,
+    };
+    
+    let stored = match requested {
+        Some(storage) => {
+            write_token(storage, &name, &mut profile, token)?;
+            storage
+        }
+        None => match write_token(TokenStorage::Keyring, &name, &mut profile, token) {
+            Ok(()) => TokenStorage::Keyring,
+            Err(keyring_error) => {
+                write_token(TokenStorage::EncryptedFile, &name, &mut profile, token).map_err(|e| anyhow!(
+                    "Could not store the API token securely.\n  Keyring: {:#}\n  Encrypted file: {:#}\n\
+                     Use --storage command, or --storage plaintext to keep it in the config file.",
+                    keyring_error, e
+                ))?;
+                TokenStorage::EncryptedFile
+            }
+        },
+    };
     
-    if config.api_token.is_empty() {
-        return Err(anyhow!("API token not found"));
+    // Don't leave the token behind where it was stored before
+    if previous.is_some_and(|previous| previous != stored) {
+        if let Ok(Some(old_store)) = secret_store(profile.storage(), &profile) {
+            let _ = old_store.delete(&name);
+        }
+    }
+    if stored != TokenStorage::Plaintext {
+        profile.api_token.clear();
     }
+    profile.token_storage = Some(stored);
+    config.profiles.insert(name, profile);
     
-    Ok(config.api_token)
+    store_config(&config)?;
+    Ok(stored)
 }
 
-/// Save the API token to the config file
-pub fn save_api_token(token: &str) -> Result<()> {
-    let config = Config {
-        api_token: token.to_string(),
-    };
+/// Write a token to one backend
+fn write_token(storage: TokenStorage, name: &str, profile: &mut Profile, token: Option<&str>) -> Result<()> {
+    match (storage, token) {
+        (TokenStorage::Command, None) => {
+            // Make sure the command works before relying on it
+            secret_store(storage, profile)?
+                .map(|store| store.get(name))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| anyhow!("The token command printed nothing"))?;
+            Ok(())
+        }
+        (TokenStorage::Command, Some(_)) => {
+            Err(anyhow!("With --storage command the token is read from --token-command, don't pass it"))
+        }
+        (_, None) => Err(anyhow!("No API token given")),
+        (TokenStorage::Plaintext, Some(token)) => {
+            profile.api_token = token.to_string();
+            Ok(())
+        }
+        (storage, Some(token)) => match secret_store(storage, profile)? {
+            Some(store) => store.set(name, token),
+            None => Ok(()),
+        },
+    }
+}
+
+/// All profiles and the name of the one in use
+pub fn list_profiles(profile: Option<&str>) -> Result<(String, BTreeMap<String, Profile>)> {
+    let config = load_config()?;
+    Ok((profile_name(&config, profile), config.profiles))
+}
+
+/// Make a profile the one used by default
+pub fn use_profile(name: &str) -> Result<()> {
+    let mut config = load_config()?;
+    if !config.profiles.contains_key(name) {
+        return Err(anyhow!("Profile '{}' not found", name));
+    }
+    config.active_profile = Some(name.to_string());
     
-    confy::store(APP_NAME, CONFIG_NAME, &config)?;
+    store_config(&config)
+}
+
+/// Delete a profile, falling back to the default profile if it was active
+pub fn remove_profile(name: &str) -> Result<()> {
+    let mut config = load_config()?;
+    let profile = config.profiles.remove(name)
+        .ok_or_else(|| anyhow!("Profile '{}' not found", name))?;
+    if let Ok(Some(store)) = secret_store(profile.storage(), &profile) {
+        let _ = store.delete(name);
+    }
+    if config.active_profile.as_deref() == Some(name) {
+        config.active_profile = None;
+    }
     
-    Ok(())
+    store_config(&config)
+}
+
+/// Effective value of a key for the profile in use
+///
+/// Flags win over environment variables, which win over the project's
+/// `.devin.toml`, which wins over the user config file.
+pub fn resolve_config_value(
+    profile: Option<&str>,
+    key: &'static ConfigKey,
+    overrides: &ClientOptions,
+) -> Result<Option<ResolvedValue>> {
+    let resolved = |value: String, source| Ok(Some(ResolvedValue { key, value, source }));
+    
+    if let (Some(flag), Some(value)) = (key.flag, flag_value(key, overrides)) {
+        return resolved(value, ValueSource::Flag(flag));
+    }
+    for name in key.env {
+        if let Some(value) = env_value(name) {
+            return resolved(value, ValueSource::Env(name));
+        }
+    }
+    
+    if let Some(project) = load_project_config()? {
+        if let Some(value) = project.settings.value(key.name)? {
+            return resolved(display_value(&value), ValueSource::ProjectFile(project.path));
+        }
+    }
+    
+    let config = load_config()?;
+    let profile = config.profiles.get(&profile_name(&config, profile)).cloned().unwrap_or_default();
+    let file = ValueSource::UserFile(config_path()?);
+    if key.name == "api_token" && profile.storage() != TokenStorage::Plaintext {
+        // Reading the token could mean a keyring unlock or a passphrase prompt
+        return resolved(format!("({})", profile.storage().describe()), file);
+    }
+    if let Some(value) = profile.value(key.name)? {
+        return resolved(display_value(&value), file);
+    }
+    if key.name == "api_url" {
+        return resolved(DEFAULT_API_URL.to_string(), ValueSource::Default);
+    }
+    Ok(None)
+}
+
+/// Effective values of every key that is set
+pub fn resolve_config_values(profile: Option<&str>, overrides: &ClientOptions) -> Result<Vec<ResolvedValue>> {
+    let mut values = Vec::new();
+    for key in CONFIG_KEYS {
+        values.extend(resolve_config_value(profile, key, overrides)?);
+    }
+    Ok(values)
+}
+
+/// Value given for a key with a global flag
+fn flag_value(key: &ConfigKey, overrides: &ClientOptions) -> Option<String> {
+    match key.name {
+        "api_url" => overrides.api_url.clone(),
+        "timeout" => overrides.timeout.map(|timeout| timeout.as_secs().to_string()),
+        "connect_timeout" => overrides.connect_timeout.map(|timeout| timeout.as_secs().to_string()),
+        "proxy" => overrides.proxy.clone(),
+        "ca_cert" => overrides.ca_cert.as_ref().map(|path| path.display().to_string()),
+        "max_attempts" => overrides.max_attempts.map(|attempts| attempts.to_string()),
+        _ => None,
+    }
+}
+
+/// Check and store a value in the profile in use, creating the profile if needed
+pub fn set_config_value(profile: Option<&str>, name: &str, value: &str) -> Result<()> {
+    let key = writable_key(name)?;
+    let value = key.parse(value)?;
+    
+    let mut config = load_config()?;
+    let profile = profile_name(&config, profile);
+    config.profiles.entry(profile).or_default().set_value(key.name, Some(value))?;
+    store_config(&config)
+}
+
+/// Remove a value from the profile in use; returns whether it was set
+pub fn unset_config_value(profile: Option<&str>, name: &str) -> Result<bool> {
+    let key = writable_key(name)?;
+    
+    let mut config = load_config()?;
+    let profile = profile_name(&config, profile);
+    let Some(profile) = config.profiles.get_mut(&profile) else {
+        return Ok(false);
+    };
+    if profile.value(key.name)?.is_none() {
+        return Ok(false);
+    }
+    profile.set_value(key.name, None)?;
+    store_config(&config)?;
+    Ok(true)
+}
+
+fn writable_key(name: &str) -> Result<&'static ConfigKey> {
+    let key = find_key(name)?;
+    if key.managed {
+        return Err(anyhow!(
+            "{} is managed by 'devin configure', which also moves the stored token",
+            key.name
+        ));
+    }
+    Ok(key)
+}
+
+/// Check the config file against the schema, listing every problem found
+pub fn check_config_file() -> Result<()> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let problems = config_problems(&contents);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!("{} is not valid:\n  {}", path.display(), problems.join("\n  ")))
+}
+
+fn config_problems(contents: &str) -> Vec<String> {
+    let document: Value = match toml::from_str(contents) {
+        Ok(document) => document,
+        Err(e) => return vec![e.to_string()],
+    };
+    let mut problems = Vec::new();
+    for (name, value) in document.as_table().into_iter().flatten() {
+        match (name.as_str(), value) {
+            ("active_profile", Value::String(_)) | ("version", Value::Integer(_)) => {}
+            ("profiles", Value::Table(profiles)) => {
+                for (profile, settings) in profiles {
+                    let Some(settings) = settings.as_table() else {
+                        problems.push(format!("profiles.{} must be a table", profile));
+                        continue;
+                    };
+                    for (key, value) in settings {
+                        // An empty token is what profiles without a plaintext token hold
+                        if key == "api_token" && value.as_str() == Some("") {
+                            continue;
+                        }
+                        let checked = find_key(key).and_then(|key| key.validate(value));
+                        if let Err(e) = checked {
+                            problems.push(format!("profiles.{}: {}", profile, e));
+                        }
+                    }
+                }
+            }
+            ("redaction", Value::Table(_)) => {
+                let checked = value.clone().try_into::<RedactionSettings>()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|settings| settings.validate());
+                if let Err(e) = checked {
+                    problems.push(format!("redaction: {}", e));
+                }
+            }
+            ("active_profile" | "profiles" | "version" | "redaction", _) => problems.push(format!("{} has a value of the wrong type", name)),
+            (name, _) if find_key(name).is_ok() => {
+                problems.push(format!("{} must be set in a profile, such as [profiles.{}]", name, DEFAULT_PROFILE));
+            }
+            (name, _) => problems.push(format!("Unknown setting '{}'", name)),
+        }
+    }
+    problems
+}
+
+/// Redaction settings in effect
+///
+/// The project's `.devin.toml` is layered over the user config, and
+/// `DEVIN_REDACTION` overrides the mode of both.
+pub fn get_redaction_settings() -> Result<RedactionSettings> {
+    let mut settings = load_config()?.redaction;
+    if let Some(project) = load_project_config()? {
+        settings = settings.merged_with(&project.redaction);
+    }
+    if let Some(mode) = env_value(REDACTION_ENV_VAR) {
+        let mode = RedactionMode::from_str(&mode, true)
+            .map_err(|_| anyhow!("{} must be mask, block or off, got '{}'", REDACTION_ENV_VAR, mode))?;
+        settings.mode = Some(mode);
+    }
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Read a non-empty environment variable
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Read an environment variable holding a number of seconds
+fn env_seconds(name: &str) -> Result<Option<Duration>> {
+    env_value(name)
+        .map(|value| {
+            value.trim()
+                .parse()
+                .map(Duration::from_secs)
+                .with_context(|| format!("{} must be a number of seconds, got '{}'", name, value))
+        })
+        .transpose()
+}
+
+/// Read an environment variable holding a value of a config key
+fn env_setting<T: serde::de::DeserializeOwned>(name: &str, key: &str) -> Result<Option<T>> {
+    env_value(name)
+        .map(|value| {
+            let value = find_key(key)?.parse(&value).with_context(|| format!("Invalid {}", name))?;
+            Ok(value.try_into()?)
+        })
+        .transpose()
+}
+
+/// Read a duration such as `500ms` from the config file or the environment
+fn parse_delay(value: &str, name: &str) -> Result<Duration> {
+    humantime::parse_duration(value.trim())
+        .with_context(|| format!("{} must be a duration such as 500ms or 2s, got '{}'", name, value))
+}
+
+/// Resolve the HTTP client options
+///
+/// Values given in `overrides` (from command-line flags) win over the
+/// `DEVIN_*` environment variables, which win over the project's
+/// `.devin.toml`, which wins over the profile in use. A profile that does not
+/// exist yet, such as one being configured, has no settings of its own.
+pub fn get_client_options(profile: Option<&str>, overrides: &ClientOptions) -> Result<ClientOptions> {
+    let config = load_config()?;
+    let profile = config.profiles.get(&profile_name(&config, profile)).cloned().unwrap_or_default();
+    let config = match load_project_config()? {
+        Some(project) => project.settings.layered_over(&profile),
+        None => profile,
+    };
+    
+    Ok(ClientOptions {
+        api_url: overrides.api_url.clone()
+            .or_else(|| env_value(API_URL_ENV_VAR))
+            .or(config.api_url),
+        timeout: match overrides.timeout {
+            Some(timeout) => Some(timeout),
+            None => env_seconds(TIMEOUT_ENV_VAR)?.or(config.timeout.map(Duration::from_secs)),
+        },
+        connect_timeout: match overrides.connect_timeout {
+            Some(timeout) => Some(timeout),
+            None => env_seconds(CONNECT_TIMEOUT_ENV_VAR)?.or(config.connect_timeout.map(Duration::from_secs)),
+        },
+        proxy: overrides.proxy.clone()
+            .or_else(|| env_value(PROXY_ENV_VAR))
+            .or(config.proxy),
+        ca_cert: overrides.ca_cert.clone()
+            .or_else(|| env_value(CA_CERT_ENV_VAR).map(PathBuf::from))
+            .or(config.ca_cert),
+        max_attempts: match overrides.max_attempts {
+            Some(attempts) => Some(attempts),
+            None => env_setting(MAX_ATTEMPTS_ENV_VAR, "max_attempts")?.or(config.max_attempts),
+        },
+        retry_base_delay: match env_value(RETRY_BASE_DELAY_ENV_VAR) {
+            Some(delay) => Some(parse_delay(&delay, RETRY_BASE_DELAY_ENV_VAR)?),
+            None => config.retry_base_delay.map(|delay| parse_delay(&delay, "retry_base_delay")).transpose()?,
+        },
+        retry_max_delay: match env_value(RETRY_MAX_DELAY_ENV_VAR) {
+            Some(delay) => Some(parse_delay(&delay, RETRY_MAX_DELAY_ENV_VAR)?),
+            None => config.retry_max_delay.map(|delay| parse_delay(&delay, "retry_max_delay")).transpose()?,
+        },
+        retry_jitter: env_setting(RETRY_JITTER_ENV_VAR, "retry_jitter")?.or(config.retry_jitter),
+        retry_non_idempotent: env_setting(RETRY_NON_IDEMPOTENT_ENV_VAR, "retry_non_idempotent")?
+            .or(config.retry_non_idempotent),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::retry::RetryPolicy;
+    use crate::config::PASSPHRASE_ENV_VAR;
     use std::env;
     use tempfile::tempdir;
     
+    fn config_with(profiles: &[(&str, Profile)]) -> Config {
+        Config {
+            profiles: profiles.iter()
+                .map(|(name, profile)| (name.to_string(), profile.clone()))
+                .collect(),
+            ..Config::default()
+        }
+    }
+    
     #[test]
     fn test_config_default() {
         let config = Config::default();
-        assert_eq!(config.api_token, "");
+        assert!(config.profiles.is_empty());
+        assert_eq!(Profile::default().api_token, "");
     }
     
     #[test]
     fn test_env_var_token() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
         // Create a temporary directory for the config file
         let temp_dir = tempdir().unwrap();
         let config_path = temp_dir.path().to_str().unwrap();
@@ -87,7 +794,7 @@ mod tests {
         env::set_var(ENV_VAR_NAME, "test-token-cli");
         
         // Get the token
-        let result = get_api_token();
+        let result = get_api_token(None);
         
         // Restore the original environment variables
         match original_token {
@@ -107,6 +814,8 @@ mod tests {
     
     #[test]
     fn test_config_roundtrip() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
         // Create a temporary directory for the config file
         let temp_dir = tempdir().unwrap();
         let config_path = temp_dir.path().to_str().unwrap();
@@ -122,14 +831,19 @@ mod tests {
         // Set a custom config path for testing and remove any token
         env::set_var("CONFY_CONFIG_PATH", &unique_config_path);
         env::remove_var(ENV_VAR_NAME);
+        env::set_var(TOKEN_STORAGE_ENV_VAR, "encrypted-file");
+        env::set_var(PASSPHRASE_ENV_VAR, "test-passphrase");
         
         // Save a token
         let token = "test-token-cli";  // Changed to match the expected value in other tests
-        let save_result = save_api_token(token);
+        let save_result = save_api_token(None, token);
         assert!(save_result.is_ok());
         
         // Get the token
-        let get_result = get_api_token();
+        let get_result = get_api_token(None);
+        let config_contents = std::fs::read_to_string(format!("{}/config.toml", unique_config_path)).unwrap();
+        env::remove_var(TOKEN_STORAGE_ENV_VAR);
+        env::remove_var(PASSPHRASE_ENV_VAR);
         
         // Restore the original environment variables
         match original_token {
@@ -145,6 +859,375 @@ mod tests {
         // Check the result
         assert!(get_result.is_ok());
         assert_eq!(get_result.unwrap(), token);
+        assert!(!config_contents.contains(token));
+    }
+    
+    #[test]
+    fn test_changing_token_storage() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::set_var(PASSPHRASE_ENV_VAR, "test-passphrase");
+        env::remove_var(ENV_VAR_NAME);
+        
+        store_api_token(None, Some("plain-token"), Some(TokenStorage::Plaintext), None).unwrap();
+        // The profile keeps its storage when no other one is asked for
+        let kept = store_api_token(None, Some("plain-token-2"), None, None);
+        let moved = store_api_token(None, Some("secret-token"), Some(TokenStorage::EncryptedFile), None);
+        let loaded = load_config();
+        let token = get_api_token(None);
+        let token_file = temp_dir.path().join("secrets").join("default.token");
+        let file_written = token_file.exists();
+        let removed = remove_profile(DEFAULT_PROFILE);
+        
+        env::remove_var(PASSPHRASE_ENV_VAR);
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert_eq!(kept.unwrap(), TokenStorage::Plaintext);
+        assert_eq!(moved.unwrap(), TokenStorage::EncryptedFile);
+        let profile = &loaded.unwrap().profiles[DEFAULT_PROFILE];
+        assert_eq!(profile.api_token, "");
+        assert_eq!(profile.token_storage, Some(TokenStorage::EncryptedFile));
+        assert_eq!(token.unwrap(), "secret-token");
+        assert!(file_written);
+        assert!(removed.is_ok());
+        assert!(!token_file.exists());
+    }
+    
+    #[test]
+    fn test_resolve_config_value_sources() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::remove_var(API_URL_ENV_VAR);
+        env::remove_var(PROXY_ENV_VAR);
+        
+        let api_url = find_key("api_url").unwrap();
+        let proxy = find_key("proxy").unwrap();
+        let default = resolve_config_value(None, api_url, &ClientOptions::default());
+        set_config_value(None, "api_url", "https://file.example.com").unwrap();
+        let from_file = resolve_config_value(None, api_url, &ClientOptions::default());
+        env::set_var(API_URL_ENV_VAR, "https://env.example.com");
+        let from_env = resolve_config_value(None, api_url, &ClientOptions::default());
+        let flags = ClientOptions {
+            api_url: Some("https://flag.example.com".to_string()),
+            ..ClientOptions::default()
+        };
+        let from_flag = resolve_config_value(None, api_url, &flags);
+        let unset_proxy = resolve_config_value(None, proxy, &ClientOptions::default());
+        
+        env::remove_var(API_URL_ENV_VAR);
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert_eq!(default.unwrap().unwrap().source, ValueSource::Default);
+        let from_file = from_file.unwrap().unwrap();
+        assert_eq!(from_file.value, "https://file.example.com");
+        assert_eq!(from_file.source, ValueSource::UserFile(temp_dir.path().join("config.toml")));
+        assert_eq!(from_env.unwrap().unwrap().source, ValueSource::Env(API_URL_ENV_VAR));
+        let from_flag = from_flag.unwrap().unwrap();
+        assert_eq!(from_flag.value, "https://flag.example.com");
+        assert_eq!(from_flag.source, ValueSource::Flag("--api-url"));
+        assert!(unset_proxy.unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_project_settings_layer_over_profile() {
+        let profile = Profile {
+            api_token: "user-token".to_string(),
+            api_url: Some("https://user.example.com".to_string()),
+            timeout: Some(10),
+            proxy: Some("http://proxy.example.com:3128".to_string()),
+            ..Profile::default()
+        };
+        let project = Profile {
+            api_url: Some("https://project.example.com".to_string()),
+            timeout: Some(60),
+            ..Profile::default()
+        };
+        
+        let merged = project.layered_over(&profile);
+        assert_eq!(merged.api_url.as_deref(), Some("https://user.example.com"));
+        assert_eq!(merged.timeout, Some(60));
+        assert_eq!(merged.proxy.as_deref(), Some("http://proxy.example.com:3128"));
+        assert_eq!(merged.api_token, "user-token");
+    }
+    
+    #[test]
+    fn test_refuses_config_from_newer_version() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let contents = "version = 99\n\n[profiles.default]\nsomething_new = true\n";
+        std::fs::write(&path, contents).unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let loaded = load_config();
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert!(format!("{:#}", loaded.unwrap_err()).contains("upgrade devin"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+    }
+    
+    #[test]
+    fn test_backup_path_keeps_earlier_backups() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        assert_eq!(backup_path(&path, 1), temp_dir.path().join("config.toml.v1.bak"));
+        std::fs::write(temp_dir.path().join("config.toml.v1.bak"), "").unwrap();
+        assert_eq!(backup_path(&path, 1), temp_dir.path().join("config.toml.v1.1.bak"));
+    }
+    
+    #[test]
+    fn test_config_problems() {
+        assert!(config_problems("[profiles.default]\napi_url = \"https://devin.example.com\"\napi_token = \"\"\n").is_empty());
+        
+        let problems = config_problems("[profiles.default]\ntimeout = \"soon\"\ncolour = \"red\"\n");
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().any(|problem| problem.contains("timeout")));
+        assert!(problems.iter().any(|problem| problem.contains("colour")));
+        
+        assert_eq!(config_problems("profiles = [").len(), 1);
+        assert!(config_problems("version = 3\napi_url = \"https://devin.example.com\"\n")[0].contains("[profiles.default]"));
+        
+        assert!(config_problems("[redaction]\nmode = \"block\"\npatterns = [\"corp-[0-9]+\"]\n").is_empty());
+        assert!(config_problems("[redaction]\npatterns = [\"(unclosed\"]\n")[0].contains("Invalid redaction pattern"));
+        assert!(config_problems("[redaction]\nmode = \"hide\"\n")[0].starts_with("redaction:"));
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_state_dir() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let saved: Vec<_> = [STATE_DIR_ENV_VAR, "XDG_STATE_HOME", "HOME"].iter()
+            .map(|name| (*name, env::var_os(name)))
+            .collect();
+        
+        env::remove_var(STATE_DIR_ENV_VAR);
+        env::set_var("HOME", "/home/dev");
+        env::set_var("XDG_STATE_HOME", "relative/state");
+        let from_home = state_dir();
+        env::set_var("XDG_STATE_HOME", "/var/state");
+        let from_xdg = state_dir();
+        env::set_var(STATE_DIR_ENV_VAR, "/tmp/devin-state");
+        let overridden = state_dir();
+        
+        for (name, value) in saved {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+        
+        assert_eq!(from_home.unwrap(), PathBuf::from("/home/dev/.local/state/devin"));
+        assert_eq!(from_xdg.unwrap(), PathBuf::from("/var/state/devin"));
+        assert_eq!(overridden.unwrap(), PathBuf::from("/tmp/devin-state"));
+    }
+    
+    #[test]
+    fn test_redaction_settings_precedence() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let config = Config {
+            redaction: RedactionSettings {
+                mode: Some(RedactionMode::Block),
+                patterns: vec!["corp-[0-9]+".to_string()],
+            },
+            ..Config::default()
+        };
+        store_config(&config).unwrap();
+        let from_file = get_redaction_settings();
+        env::set_var(REDACTION_ENV_VAR, "off");
+        let from_env = get_redaction_settings();
+        env::set_var(REDACTION_ENV_VAR, "hide");
+        let invalid = get_redaction_settings();
+        
+        env::remove_var(REDACTION_ENV_VAR);
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert_eq!(from_file.unwrap(), config.redaction);
+        let from_env = from_env.unwrap();
+        assert_eq!(from_env.mode, Some(RedactionMode::Off));
+        assert_eq!(from_env.patterns, config.redaction.patterns);
+        assert!(invalid.unwrap_err().to_string().contains("mask, block or off"));
+    }
+    
+    #[test]
+    fn test_legacy_profile_keeps_plaintext_token() {
+        let profile = Profile {
+            api_token: "legacy-token".to_string(),
+            ..Profile::default()
+        };
+        assert_eq!(profile.storage(), TokenStorage::Plaintext);
+        assert_eq!(profile_token(DEFAULT_PROFILE, &profile).unwrap().as_deref(), Some("legacy-token"));
+        assert_eq!(profile_token(DEFAULT_PROFILE, &Profile::default()).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_client_options_precedence() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let profile = Profile {
+            api_token: "test-token".to_string(),
+            api_url: Some("https://file.example.com".to_string()),
+            timeout: Some(10),
+            proxy: Some("http://file-proxy:3128".to_string()),
+            max_attempts: Some(2),
+            retry_base_delay: Some("1s".to_string()),
+            retry_jitter: Some(true),
+            ..Profile::default()
+        };
+        store_config(&config_with(&[(DEFAULT_PROFILE, profile)])).unwrap();
+        
+        env::set_var(API_URL_ENV_VAR, "https://env.example.com");
+        env::set_var(TIMEOUT_ENV_VAR, "20");
+        env::set_var(RETRY_MAX_DELAY_ENV_VAR, "5s");
+        env::set_var(RETRY_JITTER_ENV_VAR, "false");
+        
+        let overrides = ClientOptions {
+            api_url: Some("https://flag.example.com".to_string()),
+            ..ClientOptions::default()
+        };
+        let options = get_client_options(None, &overrides);
+        let retry_flag = get_client_options(None, &ClientOptions {
+            max_attempts: Some(6),
+            ..ClientOptions::default()
+        });
+        
+        env::set_var(TIMEOUT_ENV_VAR, "soon");
+        let invalid = get_client_options(None, &ClientOptions::default());
+        env::set_var(TIMEOUT_ENV_VAR, "20");
+        env::set_var(RETRY_JITTER_ENV_VAR, "sometimes");
+        let invalid_jitter = get_client_options(None, &ClientOptions::default());
+        
+        env::remove_var(API_URL_ENV_VAR);
+        env::remove_var(TIMEOUT_ENV_VAR);
+        env::remove_var(RETRY_MAX_DELAY_ENV_VAR);
+        env::remove_var(RETRY_JITTER_ENV_VAR);
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        let options = options.unwrap();
+        assert_eq!(options.api_url.as_deref(), Some("https://flag.example.com"));
+        assert_eq!(options.timeout, Some(Duration::from_secs(20)));
+        assert_eq!(options.proxy.as_deref(), Some("http://file-proxy:3128"));
+        assert_eq!(options.connect_timeout, None);
+        assert_eq!(options.retry_policy(), RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            jitter: false,
+            retry_non_idempotent: false,
+        });
+        assert_eq!(retry_flag.unwrap().max_attempts, Some(6));
+        assert!(invalid.is_err());
+        assert!(format!("{:#}", invalid_jitter.unwrap_err()).contains("DEVIN_RETRY_JITTER"));
+    }
+    
+    #[test]
+    fn test_save_api_token_keeps_other_settings() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        
+        let profile = Profile {
+            api_url: Some("https://devin.internal.example.com".to_string()),
+            ..Profile::default()
+        };
+        store_config(&config_with(&[(DEFAULT_PROFILE, profile)])).unwrap();
+        store_api_token(None, Some("new-token"), Some(TokenStorage::Plaintext), None).unwrap();
+        let loaded = load_config();
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        let loaded = &loaded.unwrap().profiles[DEFAULT_PROFILE];
+        assert_eq!(loaded.api_token, "new-token");
+        assert_eq!(loaded.api_url.as_deref(), Some("https://devin.internal.example.com"));
+    }
+    
+    #[test]
+    fn test_legacy_config_migrates_to_default_profile() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "api_token = \"legacy-token\"\napi_url = \"https://legacy.example.com\"\n").unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::remove_var(ENV_VAR_NAME);
+        
+        let token = get_api_token(None);
+        let rewritten = std::fs::read_to_string(&path).unwrap();
+        let backup = std::fs::read_to_string(temp_dir.path().join("config.toml.v1.bak")).unwrap();
+        // Loading the upgraded file again changes nothing
+        let reloaded = load_config();
+        let backups = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert_eq!(token.unwrap(), "legacy-token");
+        assert!(rewritten.starts_with(&format!("version = {}", CURRENT_CONFIG_VERSION)));
+        assert!(rewritten.contains("[profiles.default]"));
+        assert!(backup.starts_with("api_token"));
+        assert_eq!(reloaded.unwrap().version, CURRENT_CONFIG_VERSION);
+        assert_eq!(backups, 2);
+    }
+    
+    #[test]
+    fn test_profile_selection() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        let temp_dir = tempdir().unwrap();
+        env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        env::remove_var(ENV_VAR_NAME);
+        
+        let personal = Profile {
+            api_token: "personal-token".to_string(),
+            ..Profile::default()
+        };
+        let team = Profile {
+            api_token: "team-token".to_string(),
+            api_url: Some("https://staging.example.com".to_string()),
+            ..Profile::default()
+        };
+        store_config(&config_with(&[(DEFAULT_PROFILE, personal), ("team", team)])).unwrap();
+        
+        let default_token = get_api_token(None).unwrap();
+        let flag_token = get_api_token(Some("team")).unwrap();
+        env::set_var(PROFILE_ENV_VAR, "team");
+        let env_token = get_api_token(None).unwrap();
+        let env_url = get_client_options(None, &ClientOptions::default()).unwrap().api_url;
+        env::set_var(PROFILE_ENV_VAR, "missing");
+        let missing = get_api_token(None);
+        let flag_over_env = get_api_token(Some(DEFAULT_PROFILE)).unwrap();
+        env::remove_var(PROFILE_ENV_VAR);
+        let missing_flag = get_api_token(Some("missing"));
+        
+        use_profile("team").unwrap();
+        let active_token = get_api_token(None).unwrap();
+        remove_profile("team").unwrap();
+        let (active, profiles) = list_profiles(None).unwrap();
+        
+        env::remove_var("CONFY_CONFIG_PATH");
+        
+        assert_eq!(default_token, "personal-token");
+        assert_eq!(flag_token, "team-token");
+        assert_eq!(env_token, "team-token");
+        assert_eq!(env_url.as_deref(), Some("https://staging.example.com"));
+        assert!(missing.is_err());
+        assert_eq!(flag_over_env, "personal-token");
+        assert!(missing_flag.is_err());
+        assert_eq!(active_token, "team-token");
+        assert_eq!(active, DEFAULT_PROFILE);
+        assert_eq!(profiles.keys().collect::<Vec<_>>(), vec![DEFAULT_PROFILE]);
     }
     
     #[test]
@@ -17,7 +17,7 @@ mod tests {
         let test_token = "test-token-123";
         set_api_token(test_token).unwrap();
         
-        let loaded_token = get_api_token().unwrap();
+        let loaded_token = get_api_token(None).unwrap();
         assert_eq!(loaded_token, test_token);
         
         // Clean up
@@ -37,7 +37,7 @@ mod tests {
         let config = Config::default();
         save_config(&config).unwrap();
         
-        let result = get_api_token();
+        let result = get_api_token(None);
         assert!(result.is_err());
         
         // Clean up
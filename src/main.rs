@@ -1,23 +1,124 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use anyhow::Result;
-
-mod api;
-mod commands;
-mod config;
+use devin::api::client::{ApiError, ClientOptions};
+use devin::api::pagination::DEFAULT_PAGE_SIZE;
+use devin::commands::ask::AskOptions;
+use devin::commands::configure::ConfigureOptions;
+use devin::commands::session::SessionOptions;
+use devin::commands::wait::WaitOptions;
+use devin::commands::sessions::{SessionFilter, SessionSort, SortOrder};
+use devin::commands::ExitStatusError;
+use devin::config::TokenStorage;
+use devin::output::{ErrorOutput, OutputFormat};
+use devin::{commands, config};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    
+    /// Configuration profile to use [env: DEVIN_PROFILE]
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    
+    #[command(flatten)]
+    connection: ConnectionArgs,
+    
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Connection settings, overriding the `DEVIN_*` environment variables and config file
+#[derive(Args)]
+struct ConnectionArgs {
+    /// Base URL of the Devin API [env: DEVIN_API_URL]
+    #[arg(long, global = true, value_name = "URL")]
+    api_url: Option<String>,
+    
+    /// Request timeout in seconds [env: DEVIN_TIMEOUT]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    
+    /// Connection timeout in seconds [env: DEVIN_CONNECT_TIMEOUT]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+    
+    /// Proxy URL for API requests [env: DEVIN_PROXY]
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+    
+    /// PEM file with additional trusted CA certificates [env: DEVIN_CA_CERT]
+    #[arg(long, global = true, value_name = "PATH")]
+    ca_cert: Option<PathBuf>,
+    
+    /// Attempts per API request, including the first [env: DEVIN_MAX_ATTEMPTS]
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_attempts: Option<u32>,
+}
+
+impl ConnectionArgs {
+    /// The client options given as flags
+    fn overrides(&self) -> ClientOptions {
+        ClientOptions {
+            api_url: self.api_url.clone(),
+            timeout: self.timeout.map(Duration::from_secs),
+            connect_timeout: self.connect_timeout.map(Duration::from_secs),
+            proxy: self.proxy.clone(),
+            ca_cert: self.ca_cert.clone(),
+            max_attempts: self.max_attempts,
+            ..ClientOptions::default()
+        }
+    }
+    
+    /// Resolve the effective client options from flags, environment and the
+    /// config of `profile`
+    fn client_options(&self, profile: Option<&str>) -> Result<ClientOptions> {
+        config::get_client_options(profile, &self.overrides())
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
-    /// Configure the API token
+    /// Configure the API token (of the profile selected with --profile)
     Configure {
-        /// The API token to use
-        token: String,
+        /// The API token to use; prompted for when omitted (avoids shell history)
+        token: Option<String>,
+        
+        /// Read the API token from stdin
+        #[arg(long, conflicts_with_all = ["token", "token_command"])]
+        token_stdin: bool,
+        
+        /// Where to keep the token [default: keyring, falling back to encrypted-file]
+        #[arg(long, value_enum)]
+        storage: Option<TokenStorage>,
+        
+        /// Command printing the API token, e.g. 'pass show devin' (implies --storage command)
+        #[arg(long, value_name = "COMMAND", conflicts_with = "token")]
+        token_command: Option<String>,
+        
+        /// Save the token without checking it against the API
+        #[arg(long)]
+        no_verify: bool,
+        
+        /// Replace an existing token without asking
+        #[arg(short, long)]
+        force: bool,
+    },
+    
+    /// List, switch and remove configuration profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    
+    /// Read and change settings of the profile in use
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
     },
     
     /// Show the configured API token
@@ -26,34 +127,345 @@ enum Commands {
     /// Check if the CLI is set up correctly
     Doctor,
     
+    /// Send a prompt to Devin without an interactive session (for scripts and CI)
+    #[command(alias = "run")]
+    Ask {
+        /// The prompt to send; omit or use '-' to read it from stdin
+        prompt: Option<String>,
+        
+        /// Read the prompt from a file
+        #[arg(short, long, value_name = "PATH")]
+        file: Option<PathBuf>,
+        
+        /// Follow the session until Devin finishes, printing its replies as they arrive
+        #[arg(short, long)]
+        wait: bool,
+        
+        /// Give up waiting after this many seconds
+        #[arg(long, value_name = "SECONDS", requires = "wait")]
+        wait_timeout: Option<u64>,
+        
+        /// Print the new session's web address instead of its ID
+        #[arg(long, conflicts_with = "wait")]
+        print_url_only: bool,
+        
+        #[command(flatten)]
+        polling: PollArgs,
+        
+        #[command(flatten, next_help_heading = "Session options")]
+        session: SessionOptions,
+    },
+    
     /// Start an interactive session with Devin
     Session {
         /// Optional session ID to connect to an existing session
         #[arg(short, long)]
         session_id: Option<String>,
+        
+        /// Options for the sessions started from the prompt
+        #[command(flatten, next_help_heading = "Session options")]
+        session: SessionOptions,
+    },
+    
+    /// List and manage sessions
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommand,
+    },
+}
+
+/// How often to check on a session while waiting for it
+#[derive(Args)]
+struct PollArgs {
+    /// Seconds between the first status checks
+    #[arg(long, value_name = "SECONDS", default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    poll_interval: u64,
+    
+    /// Longest delay between status checks while the session is quiet
+    #[arg(long, value_name = "SECONDS", default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    max_poll_interval: u64,
+}
+
+impl PollArgs {
+    fn wait_options(&self, wait_timeout: Option<u64>) -> WaitOptions {
+        WaitOptions {
+            timeout: wait_timeout.map(Duration::from_secs),
+            poll_interval: Duration::from_secs(self.poll_interval),
+            max_poll_interval: Duration::from_secs(self.max_poll_interval),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List profiles, marking the one in use
+    #[command(alias = "ls")]
+    List,
+    
+    /// Use a profile by default
+    Use {
+        name: String,
+    },
+    
+    /// Delete a profile
+    #[command(alias = "rm")]
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the value in effect for a key
+    Get {
+        key: String,
+        
+        /// Also print where the value comes from
+        #[arg(long)]
+        show_source: bool,
+    },
+    
+    /// Set a key in the profile in use
+    Set {
+        key: String,
+        value: String,
+    },
+    
+    /// Remove a key from the profile in use
+    Unset {
+        key: String,
+    },
+    
+    /// List the settings in effect and where they come from
+    #[command(alias = "ls")]
+    List,
+    
+    /// Print the location of the config file
+    Path,
+    
+    /// Open the config file in $VISUAL or $EDITOR
+    Edit,
+}
+
+#[derive(Subcommand)]
+enum SessionsCommand {
+    /// List sessions
+    #[command(alias = "ls")]
+    List {
+        /// Only show sessions with this status (repeatable)
+        #[arg(long, value_name = "STATUS")]
+        status: Vec<String>,
+        
+        /// Only show sessions with this tag (repeatable, all must match)
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+        
+        /// Only show sessions created on or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE")]
+        created_after: Option<String>,
+        
+        /// Only show sessions created before this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_name = "DATE")]
+        created_before: Option<String>,
+        
+        /// Field to sort by
+        #[arg(long, value_enum, default_value_t = SessionSort::Created)]
+        sort: SessionSort,
+        
+        /// Sort direction
+        #[arg(long, value_enum, default_value_t = SortOrder::Desc)]
+        order: SortOrder,
+        
+        /// Show at most this many sessions [default: 50]
+        #[arg(long)]
+        limit: Option<usize>,
+        
+        /// Fetch every session instead of stopping after --limit matches
+        #[arg(long)]
+        all: bool,
+        
+        /// Number of sessions to request from the API per page
+        #[arg(long, default_value_t = DEFAULT_PAGE_SIZE, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=1000))]
+        page_size: usize,
+    },
+    
+    /// Show the details of a session
+    Show {
+        session_id: String,
+    },
+    
+    /// Open a session in the web browser
+    Open {
+        session_id: String,
+        
+        /// Print the session's web address instead of opening it
+        #[arg(long)]
+        print_url_only: bool,
+    },
+    
+    /// Open an interactive session attached to an existing session
+    Attach {
+        session_id: String,
+    },
+    
+    /// Follow a session until it finishes, fails, gets blocked or times out
+    Wait {
+        session_id: String,
+        
+        /// Give up waiting after this many seconds
+        #[arg(long, value_name = "SECONDS")]
+        wait_timeout: Option<u64>,
+        
+        #[command(flatten)]
+        polling: PollArgs,
+    },
+    
+    /// Terminate one or more sessions
+    Terminate {
+        #[arg(required = true)]
+        session_ids: Vec<String>,
+    },
+    
+    /// Add or remove tags on a session
+    Tag {
+        session_id: String,
+        
+        /// Tags to add
+        tags: Vec<String>,
+        
+        /// Tag to remove (repeatable)
+        #[arg(long, value_name = "TAG")]
+        remove: Vec<String>,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     
+    if let Err(e) = run(&cli) {
+        // Machine-readable formats get a structured error on stderr
+        let error_output = ErrorOutput::from_error(&e);
+        if error_output.eprint(cli.output) {
+            std::process::exit(error_output.exit_code);
+        }
+        
+        if let Some(exit_status) = e.downcast_ref::<ExitStatusError>() {
+            eprintln!("Error: {}", exit_status);
+            std::process::exit(exit_status.code);
+        }
+        
+        // API failures get a distinct exit code so scripts can branch on them
+        if let Some(api_error) = e.chain().find_map(|cause| cause.downcast_ref::<ApiError>()) {
+            eprintln!("Error ({}): {:#}", api_error.kind(), e);
+            if let Some(request_id) = api_error.request_id() {
+                eprintln!("Request ID: {}", request_id);
+            }
+            std::process::exit(api_error.exit_code());
+        }
+        return Err(e);
+    }
+    
+    Ok(())
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    let profile = cli.profile.as_deref();
+    
     match &cli.command {
-        Some(Commands::Configure { token }) => {
-            commands::configure::execute(token)
+        Some(Commands::Configure { token, token_stdin, storage, token_command, no_verify, force }) => {
+            let configure_options = ConfigureOptions {
+                token: token.clone(),
+                token_stdin: *token_stdin,
+                storage: *storage,
+                token_command: token_command.clone(),
+                verify: !no_verify,
+                force: *force,
+            };
+            commands::configure::execute(&configure_options, profile, &cli.connection.client_options(profile)?, cli.output)
         }
+        Some(Commands::Profile { command }) => match command {
+            ProfileCommand::List => commands::profile::list(profile, cli.output),
+            ProfileCommand::Use { name } => commands::profile::use_(name, cli.output),
+            ProfileCommand::Remove { name } => commands::profile::remove(name, cli.output),
+        },
+        Some(Commands::Config { command }) => match command {
+            ConfigCommand::Get { key, show_source } => {
+                commands::config::get(key, *show_source, profile, &cli.connection.overrides(), cli.output)
+            }
+            ConfigCommand::Set { key, value } => commands::config::set(key, value, profile, cli.output),
+            ConfigCommand::Unset { key } => commands::config::unset(key, profile, cli.output),
+            ConfigCommand::List => commands::config::list(profile, &cli.connection.overrides(), cli.output),
+            ConfigCommand::Path => commands::config::path(cli.output),
+            ConfigCommand::Edit => commands::config::edit(cli.output),
+        },
         Some(Commands::Show) => {
-            commands::show::execute()
+            commands::show::execute(profile, cli.output)
         }
         Some(Commands::Doctor) => {
-            commands::doctor::execute()
+            commands::doctor::execute(profile, &cli.connection.client_options(profile)?, cli.output)
+        }
+        Some(Commands::Ask { prompt, file, wait, wait_timeout, print_url_only, polling, session }) => {
+            let ask_options = AskOptions {
+                prompt: prompt.clone(),
+                file: file.clone(),
+                wait: wait.then(|| polling.wait_options(*wait_timeout)),
+                print_url_only: *print_url_only,
+                session: session.clone(),
+            };
+            commands::ask::execute(&ask_options, profile, &cli.connection.client_options(profile)?, cli.output)
+        }
+        Some(Commands::Session { session_id, session }) => {
+            commands::session::execute(session_id.as_deref(), session, profile, &cli.connection.client_options(profile)?, cli.output)
         }
-        Some(Commands::Session { session_id }) => {
-            commands::session::execute(session_id.as_deref())
+        Some(Commands::Sessions { command }) => {
+            run_sessions(cli, command)
         }
         None => {
             // If no command is specified, start an interactive session
-            commands::session::execute(None)
+            commands::session::execute(None, &SessionOptions::default(), profile, &cli.connection.client_options(profile)?, cli.output)
+        }
+    }
+}
+
+fn run_sessions(cli: &Cli, command: &SessionsCommand) -> Result<()> {
+    let profile = cli.profile.as_deref();
+    let options = cli.connection.client_options(profile)?;
+    
+    if let SessionsCommand::Attach { session_id } = command {
+        return commands::session::execute(Some(session_id), &SessionOptions::default(), profile, &options, cli.output);
+    }
+    
+    let api_client = commands::sessions::api_client(profile, &options, cli.output)?;
+    match command {
+        SessionsCommand::List { status, tag, created_after, created_before, sort, order, limit, all, page_size } => {
+            let filter = SessionFilter {
+                statuses: status.clone(),
+                tags: tag.clone(),
+                created_after: created_after.clone(),
+                created_before: created_before.clone(),
+                sort: *sort,
+                order: *order,
+                limit: *limit,
+            };
+            commands::sessions::list(&api_client, &filter, *all, *page_size, cli.output)
         }
+        SessionsCommand::Show { session_id } => {
+            commands::sessions::show(&api_client, session_id, cli.output)
+        }
+        SessionsCommand::Open { session_id, print_url_only } => {
+            commands::sessions::open(&api_client, session_id, *print_url_only, cli.output)
+        }
+        SessionsCommand::Wait { session_id, wait_timeout, polling } => {
+            commands::wait::print_waiting(session_id, cli.output);
+            commands::wait::wait_for_session(&api_client, session_id, &polling.wait_options(*wait_timeout), cli.output)
+        }
+        SessionsCommand::Terminate { session_ids } => {
+            commands::sessions::terminate(&api_client, session_ids, cli.output)
+        }
+        SessionsCommand::Tag { session_id, tags, remove } => {
+            commands::sessions::tag(&api_client, session_id, tags, remove, cli.output)
+        }
+        SessionsCommand::Attach { .. } => unreachable!("handled above"),
     }
 }
 
@@ -61,10 +473,26 @@ fn main() -> Result<()> {
 mod tests {
     use super::*;
     use clap::Parser;
+    use std::sync::Mutex;
+    
+    /// Serializes tests that read or change the environment; the library's
+    /// lock is only compiled into its own tests
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
     
     #[test]
     fn test_main_no_command() {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        
+        // RUST_TEST keeps the session out of the interactive loop
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::env::set_var("CONFY_CONFIG_PATH", temp_dir.path());
+        std::env::set_var("DEVIN_API_TOKEN", "test-token-123");
+        std::env::set_var("RUST_TEST", "1");
         let result = main();
+        std::env::remove_var("RUST_TEST");
+        std::env::remove_var("DEVIN_API_TOKEN");
+        std::env::remove_var("CONFY_CONFIG_PATH");
+        
         assert!(result.is_ok());
     }
     
@@ -72,11 +500,22 @@ mod tests {
     fn test_cli_parse_configure() {
         let cli = Cli::parse_from(["devin", "configure", "test-token"]);
         match cli.command {
-            Some(Commands::Configure { token }) => {
-                assert_eq!(token, "test-token");
+            Some(Commands::Configure { token, storage, .. }) => {
+                assert_eq!(token.as_deref(), Some("test-token"));
+                assert_eq!(storage, None);
+            }
+            _ => panic!("Expected Configure command"),
+        }
+        
+        let cli = Cli::parse_from(["devin", "configure", "--token-stdin", "--no-verify", "--force"]);
+        match cli.command {
+            Some(Commands::Configure { token, token_stdin, no_verify, force, .. }) => {
+                assert_eq!(token, None);
+                assert!(token_stdin && no_verify && force);
             }
             _ => panic!("Expected Configure command"),
         }
+        assert!(Cli::try_parse_from(["devin", "configure", "--token-stdin", "test-token"]).is_err());
     }
     
     #[test]
@@ -88,6 +527,185 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_cli_parse_connection_flags() {
+        let cli = Cli::parse_from([
+            "devin", "doctor",
+            "--api-url", "https://devin.internal.example.com",
+            "--timeout", "30",
+            "--proxy", "http://proxy:3128",
+            "--max-attempts", "5",
+        ]);
+        assert_eq!(cli.connection.api_url.as_deref(), Some("https://devin.internal.example.com"));
+        assert_eq!(cli.connection.timeout, Some(30));
+        assert_eq!(cli.connection.proxy.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(cli.connection.ca_cert, None);
+        assert_eq!(cli.connection.max_attempts, Some(5));
+        assert!(Cli::try_parse_from(["devin", "doctor", "--max-attempts", "0"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parse_ask() {
+        let cli = Cli::parse_from(["devin", "run", "Fix the build", "--wait", "--wait-timeout", "600"]);
+        match cli.command {
+            Some(Commands::Ask { prompt, file, wait, wait_timeout, polling, .. }) => {
+                assert_eq!(prompt.as_deref(), Some("Fix the build"));
+                assert_eq!(file, None);
+                assert!(wait);
+                assert_eq!(wait_timeout, Some(600));
+                assert_eq!(polling.poll_interval, 5);
+                assert_eq!(polling.max_poll_interval, 60);
+            }
+            _ => panic!("Expected Ask command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parse_print_url_only() {
+        let cli = Cli::parse_from(["devin", "sessions", "open", "abc", "--print-url-only"]);
+        match cli.command {
+            Some(Commands::Sessions { command: SessionsCommand::Open { session_id, print_url_only } }) => {
+                assert_eq!(session_id, "abc");
+                assert!(print_url_only);
+            }
+            _ => panic!("Expected sessions open command"),
+        }
+        
+        assert!(Cli::try_parse_from(["devin", "ask", "Fix it", "--print-url-only"]).is_ok());
+        assert!(Cli::try_parse_from(["devin", "ask", "Fix it", "--print-url-only", "--wait"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parse_session_options() {
+        let cli = Cli::parse_from([
+            "devin", "ask", "Fix the build", "--title", "CI", "--tag", "ci", "--tag", "backend",
+            "--max-acu-limit", "20", "--unlisted", "--knowledge-id", "k1", "--secret-id", "s1", "--idempotent",
+            "--attach", "build.log", "--attach", "spec/", "--with-git-context",
+        ]);
+        match cli.command {
+            Some(Commands::Ask { session, .. }) => {
+                assert_eq!(session.title.as_deref(), Some("CI"));
+                assert_eq!(session.tags, vec!["ci", "backend"]);
+                assert_eq!(session.max_acu_limit, Some(20));
+                assert!(session.unlisted);
+                assert_eq!(session.knowledge_ids, vec!["k1"]);
+                assert_eq!(session.secret_ids, vec!["s1"]);
+                assert!(session.idempotent);
+                assert_eq!(session.attachments, vec![PathBuf::from("build.log"), PathBuf::from("spec/")]);
+                assert!(session.with_git_context);
+            }
+            _ => panic!("Expected Ask command"),
+        }
+        
+        let cli = Cli::parse_from(["devin", "session", "--playbook-id", "p1"]);
+        match cli.command {
+            Some(Commands::Session { session_id, session }) => {
+                assert_eq!(session_id, None);
+                assert_eq!(session.playbook_id.as_deref(), Some("p1"));
+            }
+            _ => panic!("Expected Session command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parse_sessions_wait() {
+        let cli = Cli::parse_from(["devin", "sessions", "wait", "abc", "--wait-timeout", "30", "--poll-interval", "2"]);
+        match cli.command {
+            Some(Commands::Sessions { command: SessionsCommand::Wait { session_id, wait_timeout, polling } }) => {
+                assert_eq!(session_id, "abc");
+                let wait_options = polling.wait_options(wait_timeout);
+                assert_eq!(wait_options.timeout, Some(Duration::from_secs(30)));
+                assert_eq!(wait_options.poll_interval, Duration::from_secs(2));
+            }
+            _ => panic!("Expected sessions wait command"),
+        }
+        
+        // Doubling a zero interval would poll the API in a tight loop
+        assert!(Cli::try_parse_from(["devin", "sessions", "wait", "abc", "--poll-interval", "0"]).is_err());
+        assert!(Cli::try_parse_from(["devin", "sessions", "wait", "abc", "--max-poll-interval", "0"]).is_err());
+        assert!(Cli::try_parse_from(["devin", "ask", "Hi", "--wait", "--poll-interval", "0"]).is_err());
+    }
+    
+    #[test]
+    fn test_cli_parse_output_format() {
+        let cli = Cli::parse_from(["devin", "show", "--output", "yaml"]);
+        assert_eq!(cli.output, OutputFormat::Yaml);
+        
+        let cli = Cli::parse_from(["devin", "-o", "json", "doctor"]);
+        assert_eq!(cli.output, OutputFormat::Json);
+        
+        let cli = Cli::parse_from(["devin", "show"]);
+        assert_eq!(cli.output, OutputFormat::Table);
+    }
+    
+    #[test]
+    fn test_cli_parse_sessions_list() {
+        let cli = Cli::parse_from([
+            "devin", "sessions", "list",
+            "--status", "running", "--status", "blocked",
+            "--tag", "ci",
+            "--created-after", "2024-01-01",
+            "--sort", "updated", "--order", "asc",
+            "--limit", "10",
+            "--all", "--page-size", "25",
+        ]);
+        match cli.command {
+            Some(Commands::Sessions { command: SessionsCommand::List { status, tag, created_after, sort, order, limit, all, page_size, .. } }) => {
+                assert_eq!(status, vec!["running", "blocked"]);
+                assert_eq!(tag, vec!["ci"]);
+                assert_eq!(created_after.as_deref(), Some("2024-01-01"));
+                assert_eq!(sort, SessionSort::Updated);
+                assert_eq!(order, SortOrder::Asc);
+                assert_eq!(limit, Some(10));
+                assert!(all);
+                assert_eq!(page_size, 25);
+            }
+            _ => panic!("Expected sessions list command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parse_sessions_tag() {
+        let cli = Cli::parse_from(["devin", "sessions", "tag", "abc", "ci", "urgent", "--remove", "old"]);
+        match cli.command {
+            Some(Commands::Sessions { command: SessionsCommand::Tag { session_id, tags, remove } }) => {
+                assert_eq!(session_id, "abc");
+                assert_eq!(tags, vec!["ci", "urgent"]);
+                assert_eq!(remove, vec!["old"]);
+            }
+            _ => panic!("Expected sessions tag command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parse_profile() {
+        let cli = Cli::parse_from(["devin", "configure", "--profile", "team", "team-token"]);
+        assert_eq!(cli.profile.as_deref(), Some("team"));
+        assert!(matches!(cli.command, Some(Commands::Configure { .. })));
+        
+        let cli = Cli::parse_from(["devin", "profile", "use", "team"]);
+        match cli.command {
+            Some(Commands::Profile { command: ProfileCommand::Use { name } }) => assert_eq!(name, "team"),
+            _ => panic!("Expected profile use command"),
+        }
+    }
+    
+    #[test]
+    fn test_cli_parse_config() {
+        let cli = Cli::parse_from(["devin", "config", "set", "timeout", "30"]);
+        match cli.command {
+            Some(Commands::Config { command: ConfigCommand::Set { key, value } }) => {
+                assert_eq!(key, "timeout");
+                assert_eq!(value, "30");
+            }
+            _ => panic!("Expected config set command"),
+        }
+        
+        let cli = Cli::parse_from(["devin", "--api-url", "https://flag.example.com", "config", "get", "api_url", "--show-source"]);
+        assert_eq!(cli.connection.overrides().api_url.as_deref(), Some("https://flag.example.com"));
+        assert!(matches!(cli.command, Some(Commands::Config { command: ConfigCommand::Get { show_source: true, .. } })));
+    }
+    
     #[test]
     fn test_cli_parse_doctor() {
         let cli = Cli::parse_from(["devin", "doctor"]);
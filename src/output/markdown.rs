@@ -0,0 +1,691 @@
+use colored::{ColoredString, Colorize};
+use console::measure_text_width;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+/// Width used when the terminal's size cannot be read
+const DEFAULT_WIDTH: usize = 80;
+
+/// Narrowest width text is wrapped to, however small the terminal
+const MIN_WIDTH: usize = 20;
+
+/// Theme of the highlighted code blocks, one of syntect's bundled themes
+const CODE_THEME: &str = "base16-ocean.dark";
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn code_theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| ThemeSet::load_defaults().themes.remove(CODE_THEME).expect("a bundled theme"))
+}
+
+/// Renders Devin's markdown replies for the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownRenderer {
+    width: usize,
+    styled: bool,
+}
+
+impl MarkdownRenderer {
+    /// Renderer wrapping to `width` columns; without `styled`, text is left as it is
+    pub fn new(width: usize, styled: bool) -> Self {
+        Self {
+            width: width.max(MIN_WIDTH),
+            styled,
+        }
+    }
+    
+    /// Renderer for stdout: styled on a terminal, plain when piped or when
+    /// `NO_COLOR` is set
+    pub fn for_stdout() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let styled = io::stdout().is_terminal() && !no_color && colored::control::SHOULD_COLORIZE.should_colorize();
+        let width = console::Term::stdout()
+            .size_checked()
+            .map(|(_, columns)| columns as usize)
+            .unwrap_or(DEFAULT_WIDTH);
+        Self::new(width, styled)
+    }
+    
+    pub fn is_styled(&self) -> bool {
+        self.styled
+    }
+    
+    /// Render `markdown`, ending with a newline unless it is empty
+    pub fn render(&self, markdown: &str) -> String {
+        if !self.styled {
+            return markdown.to_string();
+        }
+        let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let mut writer = Writer::new(self.width);
+        for event in Parser::new_ext(markdown, options) {
+            writer.event(event);
+        }
+        writer.finish()
+    }
+}
+
+/// Renders a reply that arrives in pieces, a markdown block at a time
+///
+/// Markdown can't be rendered before a block is complete (a table needs all
+/// its rows), so styled text is held back until a blank line or the closing
+/// fence of a code block. Unstyled text is passed through as it comes.
+pub struct MarkdownStream {
+    renderer: MarkdownRenderer,
+    pending: String,
+    /// Length of `pending` already looked at for the end of a block
+    scanned: usize,
+    /// Fence of the code block `pending` ends in, such as `` ``` ``
+    fence: Option<String>,
+    /// Whether a block was written, so the next one is set apart
+    written: bool,
+}
+
+impl MarkdownStream {
+    pub fn new(renderer: MarkdownRenderer) -> Self {
+        Self {
+            renderer,
+            pending: String::new(),
+            scanned: 0,
+            fence: None,
+            written: false,
+        }
+    }
+    
+    /// Add a piece of the reply, returning what can be shown now
+    pub fn push(&mut self, text: &str) -> String {
+        if !self.renderer.is_styled() {
+            return text.to_string();
+        }
+        self.pending.push_str(text);
+        let mut block_end = None;
+        while let Some(newline) = self.pending[self.scanned..].find('\n') {
+            let line_end = self.scanned + newline + 1;
+            let line = self.pending[self.scanned..line_end].to_string();
+            if self.ends_block(&line) {
+                block_end = Some(line_end);
+            }
+            self.scanned = line_end;
+        }
+        match block_end {
+            Some(end) => {
+                let block: String = self.pending.drain(..end).collect();
+                self.scanned -= end;
+                self.write(&block)
+            }
+            None => String::new(),
+        }
+    }
+    
+    /// Everything still held back, once a message is complete
+    pub fn finish(&mut self) -> String {
+        let block = std::mem::take(&mut self.pending);
+        self.scanned = 0;
+        self.fence = None;
+        self.write(&block)
+    }
+    
+    /// Whether `line`, a complete line of the pending text, ends a block
+    fn ends_block(&mut self, line: &str) -> bool {
+        let trimmed = line.trim();
+        if let Some(fence) = &self.fence {
+            let marker = fence.chars().next().unwrap_or('`');
+            let closes = trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker);
+            if closes {
+                self.fence = None;
+            }
+            return closes;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent < 4 && (trimmed.starts_with("```") || trimmed.starts_with("~~~")) {
+            let marker = trimmed.chars().next().unwrap_or('`');
+            self.fence = Some(trimmed.chars().take_while(|&c| c == marker).collect());
+            return false;
+        }
+        trimmed.is_empty()
+    }
+    
+    fn write(&mut self, block: &str) -> String {
+        if block.trim().is_empty() {
+            return String::new();
+        }
+        let rendered = self.renderer.render(block);
+        if !self.renderer.is_styled() {
+            return rendered;
+        }
+        let separator = if std::mem::replace(&mut self.written, true) { "\n" } else { "" };
+        format!("{}{}", separator, rendered)
+    }
+}
+
+/// A word of a paragraph, styled, or a forced line break
+enum Piece {
+    Word { text: String, width: usize, space_before: bool },
+    Break,
+}
+
+#[derive(Default)]
+struct InlineStyle {
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    code: bool,
+    link: bool,
+    heading: Option<HeadingLevel>,
+}
+
+impl InlineStyle {
+    fn apply(&self, word: &str) -> String {
+        let mut styled: ColoredString = word.normal();
+        if self.code {
+            styled = styled.cyan();
+        }
+        if self.link {
+            styled = styled.blue().underline();
+        }
+        if self.strong > 0 {
+            styled = styled.bold();
+        }
+        if self.emphasis > 0 {
+            styled = styled.italic();
+        }
+        if self.strikethrough > 0 {
+            styled = styled.strikethrough();
+        }
+        styled = match self.heading {
+            Some(HeadingLevel::H1) => styled.bold().underline(),
+            Some(HeadingLevel::H2) => styled.bold(),
+            Some(_) => styled.bold().italic(),
+            None => styled,
+        };
+        styled.to_string()
+    }
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+}
+
+/// A link being written, to show its address after its text
+struct Link {
+    url: String,
+    text: String,
+}
+
+/// Turns markdown events into terminal lines
+struct Writer {
+    width: usize,
+    lines: Vec<String>,
+    words: Vec<Piece>,
+    pending_space: bool,
+    style: InlineStyle,
+    /// Next number of each list, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    /// Column where the text of each open list item starts
+    item_indents: Vec<usize>,
+    /// Marker of a list item whose first line is not written yet
+    marker: Option<String>,
+    quote_depth: usize,
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    link: Option<Link>,
+}
+
+impl Writer {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            lines: Vec::new(),
+            words: Vec::new(),
+            pending_space: false,
+            style: InlineStyle::default(),
+            lists: Vec::new(),
+            item_indents: Vec::new(),
+            marker: None,
+            quote_depth: 0,
+            code: None,
+            table: None,
+            link: None,
+        }
+    }
+    
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code {
+                Some((_, code)) => code.push_str(&text),
+                None => self.text(&text),
+            },
+            Event::Code(code) => {
+                self.style.code = true;
+                self.text(&code);
+                self.style.code = false;
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.text(&html),
+            Event::SoftBreak => self.pending_space = true,
+            Event::HardBreak => self.words.push(Piece::Break),
+            Event::Rule => {
+                self.flush();
+                self.blank_line();
+                let rule = "─".repeat(self.width.saturating_sub(self.prefix_width()).min(DEFAULT_WIDTH));
+                self.lines.push(format!("{}{}", self.prefix(), rule.dimmed()));
+            }
+            Event::TaskListMarker(checked) => {
+                self.text(if checked { "[x]" } else { "[ ]" });
+                self.pending_space = true;
+            }
+            _ => {}
+        }
+    }
+    
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            // The first paragraph of a list item goes after its marker
+            Tag::Paragraph if self.marker.is_none() => self.blank_line(),
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.blank_line();
+                self.style.heading = Some(level);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.blank_line();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                self.blank_line();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => if self.lists.len() > 1 { "◦ " } else { "• " }.to_string(),
+                };
+                let indent = self.item_indents.last().copied().unwrap_or(0);
+                self.item_indents.push(indent + measure_text_width(&marker));
+                self.marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.blank_line();
+                self.table = Some(Table { alignments, rows: Vec::new(), row: Vec::new() });
+            }
+            Tag::Emphasis => self.style.emphasis += 1,
+            Tag::Strong => self.style.strong += 1,
+            Tag::Strikethrough => self.style.strikethrough += 1,
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.style.link = true;
+                self.link = Some(Link { url: dest_url.to_string(), text: String::new() });
+            }
+            _ => {}
+        }
+    }
+    
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.style.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth -= 1;
+            }
+            TagEnd::CodeBlock => {
+                if let Some((language, code)) = self.code.take() {
+                    self.code_block(&language, &code);
+                }
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.item_indents.pop();
+                self.marker = None;
+            }
+            TagEnd::TableCell => {
+                let cell = self.take_words().join(" ");
+                if let Some(table) = &mut self.table {
+                    table.row.push(cell);
+                }
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.write_table(&table);
+                }
+            }
+            TagEnd::Emphasis => self.style.emphasis -= 1,
+            TagEnd::Strong => self.style.strong -= 1,
+            TagEnd::Strikethrough => self.style.strikethrough -= 1,
+            TagEnd::Link | TagEnd::Image => {
+                self.style.link = false;
+                if let Some(link) = self.link.take() {
+                    if !link.url.is_empty() && link.text != link.url {
+                        self.pending_space = true;
+                        self.push_word(&format!("({})", link.url).dimmed().to_string(), link.url.len() + 2);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Add inline text, styled a word at a time so that wrapping keeps styles
+    fn text(&mut self, text: &str) {
+        if let Some(link) = &mut self.link {
+            link.text.push_str(text);
+        }
+        let mut word = String::new();
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !word.is_empty() {
+                    self.push_styled(&word);
+                    word.clear();
+                }
+                self.pending_space = true;
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            self.push_styled(&word);
+        }
+    }
+    
+    fn push_styled(&mut self, word: &str) {
+        let styled = self.style.apply(word);
+        self.push_word(&styled, measure_text_width(word));
+    }
+    
+    fn push_word(&mut self, text: &str, width: usize) {
+        let space_before = std::mem::take(&mut self.pending_space) && !self.words.is_empty();
+        self.words.push(Piece::Word { text: text.to_string(), width, space_before });
+    }
+    
+    /// Words gathered so far, each on its own and without wrapping
+    fn take_words(&mut self) -> Vec<String> {
+        self.pending_space = false;
+        let mut words: Vec<String> = Vec::new();
+        for piece in std::mem::take(&mut self.words) {
+            match piece {
+                Piece::Word { text, space_before: false, .. } if !words.is_empty() => {
+                    words.last_mut().expect("not empty").push_str(&text);
+                }
+                Piece::Word { text, .. } => words.push(text),
+                Piece::Break => {}
+            }
+        }
+        words
+    }
+    
+    /// Start of every line: block quote bars
+    fn prefix(&self) -> String {
+        "│ ".repeat(self.quote_depth).dimmed().to_string()
+    }
+    
+    fn prefix_width(&self) -> usize {
+        2 * self.quote_depth
+    }
+    
+    fn indent(&self) -> usize {
+        self.item_indents.last().copied().unwrap_or(0)
+    }
+    
+    /// Separate blocks with an empty line, but not at the start
+    fn blank_line(&mut self) {
+        if self.lines.last().is_some_and(|line| !measure_text_width(line).eq(&self.prefix_width())) {
+            self.lines.push(self.prefix().trim_end().to_string());
+        }
+    }
+    
+    /// Wrap the words gathered so far into lines
+    fn flush(&mut self) {
+        if self.words.is_empty() {
+            return;
+        }
+        let indent = self.indent();
+        let first = match self.marker.take() {
+            Some(marker) => format!("{}{}", " ".repeat(indent - measure_text_width(&marker)), marker.dimmed()),
+            None => " ".repeat(indent),
+        };
+        let rest = " ".repeat(indent);
+        let available = self.width.saturating_sub(self.prefix_width() + indent).max(MIN_WIDTH / 2);
+        
+        let prefix = self.prefix();
+        let words = std::mem::take(&mut self.words);
+        self.pending_space = false;
+        for (index, line) in wrap(&words, available).into_iter().enumerate() {
+            let lead = if index == 0 { &first } else { &rest };
+            self.lines.push(format!("{}{}{}", prefix, lead, line));
+        }
+    }
+    
+    fn code_block(&mut self, language: &str, code: &str) {
+        let syntaxes = syntaxes();
+        let syntax = syntaxes.find_syntax_by_token(language).unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, code_theme());
+        let lead = format!("{}{}", self.prefix(), " ".repeat(self.indent()));
+        for line in LinesWithEndings::from(code) {
+            let highlighted = match highlighter.highlight_line(line, syntaxes) {
+                Ok(ranges) => {
+                    format!("{}\x1b[0m", as_24_bit_terminal_escaped(&ranges, false).trim_end_matches(['\n', '\r']))
+                }
+                Err(_) => line.trim_end_matches(['\n', '\r']).to_string(),
+            };
+            self.lines.push(format!("{}{}", lead, highlighted));
+        }
+    }
+    
+    fn write_table(&mut self, table: &Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table.rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| measure_text_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let lead = format!("{}{}", self.prefix(), " ".repeat(self.indent()));
+        
+        for (index, row) in table.rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|column| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or_default();
+                    let padding = widths[column] - measure_text_width(cell);
+                    let cell = if index == 0 { cell.bold().to_string() } else { cell.to_string() };
+                    match table.alignments.get(column) {
+                        Some(Alignment::Right) => format!("{}{}", " ".repeat(padding), cell),
+                        Some(Alignment::Center) => {
+                            format!("{}{}{}", " ".repeat(padding / 2), cell, " ".repeat(padding - padding / 2))
+                        }
+                        _ => format!("{}{}", cell, " ".repeat(padding)),
+                    }
+                })
+                .collect();
+            self.lines.push(format!("{}{}", lead, cells.join(&" │ ".dimmed().to_string()).trim_end()));
+            if index == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.lines.push(format!("{}{}", lead, rule.join("─┼─").dimmed()));
+            }
+        }
+    }
+    
+    fn finish(mut self) -> String {
+        self.flush();
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let mut output = self.lines.join("\n");
+        output.push('\n');
+        output
+    }
+}
+
+/// Wrap words to `width` columns; words joined without a space stay together
+fn wrap(words: &[Piece], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for piece in words {
+        match piece {
+            Piece::Break => {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            Piece::Word { text, width: word_width, space_before } => {
+                let space = usize::from(*space_before && line_width > 0);
+                if *space_before && line_width > 0 && line_width + space + word_width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                } else if space == 1 {
+                    line.push(' ');
+                    line_width += 1;
+                }
+                line.push_str(text);
+                line_width += word_width;
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Render and drop the escape codes, whether or not colors are enabled
+    fn render(markdown: &str, width: usize) -> String {
+        console::strip_ansi_codes(&MarkdownRenderer::new(width, true).render(markdown)).to_string()
+    }
+    
+    #[test]
+    fn test_plain_fallback_leaves_text_alone() {
+        let markdown = "# Done\n\n**All** tests pass.\n";
+        assert_eq!(MarkdownRenderer::new(80, false).render(markdown), markdown);
+    }
+    
+    #[test]
+    fn test_headings_paragraphs_and_wrapping() {
+        let markdown = "# Build fixed\n\nThe **lockfile** was stale, so `cargo build` picked up an old version of *serde*.\nI regenerated it.";
+        assert_eq!(
+            render(markdown, 40),
+            "Build fixed\n\nThe lockfile was stale, so cargo build\npicked up an old version of serde. I\nregenerated it.\n"
+        );
+    }
+    
+    #[test]
+    fn test_lists() {
+        let markdown = "Steps:\n\n1. Update the lockfile\n2. Run the tests, which takes a few minutes on CI\n   - unit\n   - integration\n\n- [x] done\n- [ ] todo\n";
+        assert_eq!(
+            render(markdown, 40),
+            "Steps:\n\n\
+             1. Update the lockfile\n\
+             2. Run the tests, which takes a few\n   minutes on CI\n   ◦ unit\n   ◦ integration\n\n\
+             • [x] done\n• [ ] todo\n"
+        );
+    }
+    
+    #[test]
+    fn test_links_and_quotes() {
+        assert_eq!(
+            render("See [the PR](https://github.com/acme/widgets/pull/7) or <https://example.com>.", 80),
+            "See the PR (https://github.com/acme/widgets/pull/7) or https://example.com.\n"
+        );
+        assert_eq!(render("> Quoted\n> text", 80), "│ Quoted text\n");
+    }
+    
+    #[test]
+    fn test_tables() {
+        let markdown = "| Test | Result |\n|------|-------:|\n| unit | 118 |\n| integration | 21 |\n";
+        assert_eq!(
+            render(markdown, 80),
+            "Test        │ Result\n────────────┼───────\nunit        │    118\nintegration │     21\n"
+        );
+    }
+    
+    #[test]
+    fn test_stream_renders_complete_blocks() {
+        let mut stream = MarkdownStream::new(MarkdownRenderer::new(80, true));
+        let mut shown = Vec::new();
+        for delta in ["# Plan", "\n\nFirst, ", "update the **lock", "file**.\n", "\n```sh\ncargo ", "update\n\ncargo test\n", "``", "`\n| A | B |\n", "|---|---|\n| 1 | 2 |"] {
+            shown.push(console::strip_ansi_codes(&stream.push(delta)).to_string());
+        }
+        shown.push(console::strip_ansi_codes(&stream.finish()).to_string());
+        assert_eq!(
+            shown,
+            vec![
+                "",
+                "Plan\n",
+                "",
+                "",
+                "\nFirst, update the lockfile.\n",
+                "",
+                "",
+                "\ncargo update\n\ncargo test\n",
+                "",
+                "\nA │ B\n──┼──\n1 │ 2\n",
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_stream_passes_plain_text_through() {
+        let mut stream = MarkdownStream::new(MarkdownRenderer::new(80, false));
+        assert_eq!(stream.push("# Pl"), "# Pl");
+        assert_eq!(stream.push("an\n"), "an\n");
+        assert_eq!(stream.finish(), "");
+    }
+    
+    #[test]
+    fn test_code_blocks_are_highlighted_not_wrapped() {
+        let markdown = "Run:\n\n```rust\nfn main() { println!(\"a long line that is not wrapped at all\"); }\n```\n";
+        let rendered = MarkdownRenderer::new(30, true).render(markdown);
+        assert!(rendered.contains("\x1b[38;2;"), "{:?}", rendered);
+        assert_eq!(
+            console::strip_ansi_codes(&rendered),
+            "Run:\n\nfn main() { println!(\"a long line that is not wrapped at all\"); }\n"
+        );
+    }
+}
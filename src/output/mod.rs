@@ -0,0 +1,5 @@
+mod markdown;
+mod render;
+
+pub use markdown::*;
+pub use render::*;
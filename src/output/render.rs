@@ -0,0 +1,221 @@
+use crate::api::client::ApiError;
+use crate::api::models::SessionDetails;
+use crate::commands::ExitStatusError;
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text and aligned tables
+    #[default]
+    Table,
+    /// JSON, for scripts and tooling
+    Json,
+    /// YAML, for scripts and tooling
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Whether output is meant for humans rather than machines
+    pub fn is_human(self) -> bool {
+        self == OutputFormat::Table
+    }
+}
+
+/// Serialize a value as JSON or YAML
+///
+/// Returns `None` for the table format, which every command renders itself.
+pub fn serialize<T: Serialize + ?Sized>(format: OutputFormat, value: &T) -> Result<Option<String>> {
+    let text = match format {
+        OutputFormat::Table => return Ok(None),
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Yaml => serde_yaml::to_string(value)?.trim_end().to_string(),
+    };
+    Ok(Some(text))
+}
+
+/// Print a value to stdout as JSON or YAML; returns `false` for the table format
+pub fn print_value<T: Serialize + ?Sized>(format: OutputFormat, value: &T) -> Result<bool> {
+    match serialize(format, value)? {
+        Some(text) => {
+            println!("{}", text);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ErrorOutput {
+    pub kind: String,
+    pub message: String,
+    pub exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorEnvelope<'a> {
+    error: &'a ErrorOutput,
+}
+
+impl ErrorOutput {
+    /// Write the error to stderr as `{"error": {...}}`; returns `false` for the table format
+    pub fn eprint(&self, format: OutputFormat) -> bool {
+        match serialize(format, &ErrorEnvelope { error: self }) {
+            Ok(Some(text)) => {
+                eprintln!("{}", text);
+                true
+            }
+            _ => false,
+        }
+    }
+    
+
+    /// Describe a command failure, picking up the kind and exit code of known errors
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        let message = format!("{:#}", error);
+        
+        if let Some(exit_status) = error.downcast_ref::<ExitStatusError>() {
+            return Self {
+                kind: "session".to_string(),
+                message,
+                exit_code: exit_status.code,
+                request_id: None,
+            };
+        }
+        if let Some(api_error) = error.chain().find_map(|cause| cause.downcast_ref::<ApiError>()) {
+            return Self {
+                kind: api_error.kind().to_string(),
+                message,
+                exit_code: api_error.exit_code(),
+                request_id: api_error.request_id().map(str::to_string),
+            };
+        }
+        
+        Self {
+            kind: "error".to_string(),
+            message,
+            exit_code: 1,
+            request_id: None,
+        }
+    }
+}
+
+/// A table with left-aligned columns sized to their widest cell
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+    
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+    
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|header| header.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        
+        let format_row = |cells: &[String]| {
+            let padded: Vec<String> = cells.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        
+        let mut lines = vec![format_row(&self.headers)];
+        lines.extend(self.rows.iter().map(|row| format_row(row)));
+        lines.join("\n")
+    }
+}
+
+/// Render sessions as a table for humans
+pub fn session_table(sessions: &[SessionDetails]) -> Table {
+    let mut table = Table::new(&["SESSION ID", "STATUS", "CREATED", "UPDATED", "TAGS"]);
+    for session in sessions {
+        table.add_row(vec![
+            session.session_id.clone(),
+            session.status.clone(),
+            session.created_at.clone(),
+            session.updated_at.clone().unwrap_or_else(|| "-".to_string()),
+            session.tags.join(","),
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Serialize)]
+    struct Sample {
+        session_id: String,
+        done: bool,
+    }
+    
+    fn sample() -> Sample {
+        Sample {
+            session_id: "abc".to_string(),
+            done: true,
+        }
+    }
+    
+    #[test]
+    fn test_serialize_json_and_yaml() {
+        let json = serialize(OutputFormat::Json, &sample()).unwrap().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["session_id"], "abc");
+        assert_eq!(value["done"], true);
+        
+        let yaml = serialize(OutputFormat::Yaml, &sample()).unwrap().unwrap();
+        assert_eq!(yaml, "session_id: abc\ndone: true");
+        
+        assert!(serialize(OutputFormat::Table, &sample()).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_table_alignment() {
+        let mut table = Table::new(&["SESSION ID", "STATUS"]);
+        table.add_row(vec!["devin-1".to_string(), "running".to_string()]);
+        table.add_row(vec!["devin-123456789".to_string(), "finished".to_string()]);
+        
+        assert_eq!(
+            table.render(),
+            "SESSION ID       STATUS\n\
+             devin-1          running\n\
+             devin-123456789  finished"
+        );
+    }
+    
+    #[test]
+    fn test_error_output_from_api_error() {
+        let error = anyhow::Error::new(ApiError::NotFound {
+            message: Some("Session not found".to_string()),
+            request_id: Some("req-1".to_string()),
+        });
+        let output = ErrorOutput::from_error(&error);
+        assert_eq!(output.kind, "not_found");
+        assert_eq!(output.exit_code, 4);
+        assert_eq!(output.request_id.as_deref(), Some("req-1"));
+        
+        let output = ErrorOutput::from_error(&anyhow::anyhow!("boom"));
+        assert_eq!(output.kind, "error");
+        assert_eq!(output.exit_code, 1);
+    }
+}
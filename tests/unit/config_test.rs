@@ -1,13 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use devin::config::{Config, get_api_token, save_api_token};
+    use devin::config::migrate::CURRENT_CONFIG_VERSION;
+    use devin::config::{Config, Profile, get_api_token, save_api_token};
     use std::env;
     use tempfile::tempdir;
     
     #[test]
     fn test_config_default() {
         let config = Config::default();
-        assert_eq!(config.api_token, "");
+        assert_eq!(config.version, CURRENT_CONFIG_VERSION);
+        assert!(config.profiles.is_empty());
+        assert_eq!(Profile::default().api_token, "");
     }
     
     #[test]
@@ -29,7 +32,7 @@ mod tests {
         env::set_var("DEVIN_API_TOKEN", "test-token-cli");
         
         // Get the token
-        let result = get_api_token();
+        let result = get_api_token(None);
         
         // Restore the original environment variables
         match original_token {
@@ -64,14 +67,16 @@ mod tests {
         // Set a custom config path for testing and remove any token
         env::set_var("CONFY_CONFIG_PATH", &unique_config_path);
         env::remove_var("DEVIN_API_TOKEN");
+        env::set_var("DEVIN_TOKEN_STORAGE", "plaintext");
         
         // Save a token
         let token = "test-token-456";
-        let save_result = save_api_token(token);
+        let save_result = save_api_token(None, token);
         assert!(save_result.is_ok());
         
         // Get the token
-        let get_result = get_api_token();
+        let get_result = get_api_token(None);
+        env::remove_var("DEVIN_TOKEN_STORAGE");
         
         // Restore the original environment variables
         match original_token {